@@ -1,9 +1,16 @@
+use crate::polygon;
+
 const NORTH: u8 = 0b0001;
 const EAST: u8 = 0b0010;
 const SOUTH: u8 = 0b0100;
 const WEST: u8 = 0b1000;
 
-pub fn solution(input: &str) -> (i32, i32) {
+// The tiles of a loop with the direction each is left in
+type Path = Vec<(i32, i32, u8)>;
+
+// Parses the maze and finds the main loop, returning the dimensions, the pipes with the start tile replaced by its
+//  inferred shape, and the path of the loop
+fn trace(input: &str) -> (usize, usize, Vec<u8>, Path) {
     // Find dimensions
     let width = input.lines().next().unwrap().chars().count();
     let height = input.lines().count();
@@ -13,11 +20,6 @@ pub fn solution(input: &str) -> (i32, i32) {
         }
         return Some((y as usize) * width + (x as usize));
     };
-    let from_index = |index: usize| {
-        let x = index % width;
-        let y = (index - x) / width;
-        return (x, y);
-    };
 
     // Build maze
     let mut start_x = 0;
@@ -46,9 +48,8 @@ pub fn solution(input: &str) -> (i32, i32) {
             };
         });
     });
-    let start_x = start_x;
-    let start_y = start_y;
-    let maze = maze;
+    let start_x = start_x as i32;
+    let start_y = start_y as i32;
 
     // Find loop
     let dir_x = |direction: u8| -> i32 {
@@ -78,80 +79,98 @@ pub fn solution(input: &str) -> (i32, i32) {
             _ => panic!("can only inverse direction with exactly one flag set")
         }
     };
-    let side = |direction: u8| -> u8 {
-        match direction {
-            NORTH => EAST,
-            EAST => SOUTH,
-            SOUTH => WEST,
-            WEST => NORTH,
-            // NORTH => WEST,
-            // EAST => NORTH,
-            // SOUTH => EAST,
-            // WEST => SOUTH,
-            _ => panic!("can only find left_hand of direction with exactly one flag set")
+
+    // Walks the loop from the start in the direction, returning each tile with the direction it is left in, or None if
+    //  the pipes do not lead back to the start
+    let walk = |maze: &Vec<u8>, mut direction: u8| -> Option<Path> {
+        let mut path = vec![];
+        let mut x = start_x;
+        let mut y = start_y;
+        while path.is_empty() || x != start_x || y != start_y {
+            path.push((x, y, direction));
+            let next_x = x + dir_x(direction);
+            let next_y = y + dir_y(direction);
+            let next_pipe = maze[index_at(next_x, next_y)?];
+            if next_pipe & inverse(direction) == 0 || path.len() > maze.len() {
+                return None;
+            }
+            x = next_x;
+            y = next_y;
+            direction = inverse(direction) ^ next_pipe;
         }
+        return Some(path);
     };
-    let mut direction = SOUTH;
-    let mut distance = 0;
-    let mut x = start_x as i32;
-    let mut y = start_y as i32;
-    let mut is_pipe = vec![false; width * height];
-    let mut is_inside = vec![false; width * height];
-    while distance == 0 || (x != (start_x as i32) || y != (start_y as i32)) {
-        let curr_index = index_at(x, y).unwrap();
-        is_pipe[curr_index] = true;
-        if is_inside[curr_index] {
-            is_inside[curr_index] = false;
-        }
-        let left_dir = side(direction);
-        let left_index = index_at(x + dir_x(left_dir), y + dir_y(left_dir));
-        if !left_index.is_none() && !is_pipe[left_index.unwrap()] && !is_inside[left_index.unwrap()] {
-            is_inside[left_index.unwrap()] = true;
-        }
-        let left_index_2 = index_at(x + dir_x(left_dir) + dir_x(direction), y + dir_y(left_dir) + dir_y(direction));
-        if !left_index_2.is_none() && !is_pipe[left_index_2.unwrap()] && !is_inside[left_index_2.unwrap()] {
-            is_inside[left_index_2.unwrap()] = true;
-        }
 
-        // find next x, y, and direction
-        distance += 1;
-        let next_x = x + dir_x(direction);
-        let next_y = y + dir_y(direction);
-        let next_index = index_at(next_x, next_y);
-        if next_index.is_none() {
-            panic!("cannot move outside the map");
-        }
-        let next_direction = inverse(direction) ^ maze[next_index.unwrap()];
+    // Infer the shape of the start tile from the neighbors that connect to it, if more than two neighbors connect then
+    //  use the first pair that forms a loop
+    let connected: Vec<u8> = [NORTH, EAST, SOUTH, WEST].into_iter().filter(|direction| {
+        index_at(start_x + dir_x(*direction), start_y + dir_y(*direction))
+            .is_some_and(|index| maze[index] & inverse(*direction) > 0)
+    }).collect();
+    let (shape, path) = connected.iter().enumerate()
+        .flat_map(|(i, a)| connected[i + 1..].iter().map(move |b| *a | *b))
+        .find_map(|shape| {
+            let mut maze = maze.clone();
+            maze[index_at(start_x, start_y).unwrap()] = shape;
+            let path = walk(&maze, shape & shape.wrapping_neg())?;
+            Some((shape, path))
+        })
+        .expect("the start is not part of a loop");
+    maze[index_at(start_x, start_y).unwrap()] = shape;
+    return (width, height, maze, path);
+}
 
-        x = next_x;
-        y = next_y;
-        direction = next_direction;
-    }
+pub fn solution(input: &str) -> (i32, i32) {
+    let (_, _, _, path) = trace(input);
 
-    // Flood fill the inside
-    let mut is_visited = vec![false; width * height];
-    let mut visit_count = 0;
-    let mut open: Vec<usize> = is_inside.iter().enumerate()
-        .filter_map(|(index, value)| if *value {Some(index)} else {None}).collect();
-    while !open.is_empty() {
-        visit_count += 1;
-        let index = open.pop().unwrap();
-        is_visited[index] = true;
-        let (x, y) = from_index(index);
-        let x = x as i32;
-        let y = y as i32;
-        let mut try_visit = |x, y| {
-            let index = index_at(x, y);
-            if !index.is_none() && !is_pipe[index.unwrap()] && !is_visited[index.unwrap()] && !open.contains(&index.unwrap()) {
-                open.push(index.unwrap());
+    // The tiles enclosed by the loop are the grid points inside the polygon through the center of each loop tile
+    let vertices: Vec<(i64, i64)> = path.iter().map(|(x, y, _)| (*x as i64, *y as i64)).collect();
+    let enclosed = polygon::interior_points(&vertices) as i32;
+    let distance = path.len() as i32;
+    return ((distance + 1) / 2, enclosed);
+}
+
+// Draws the maze with the main loop in box-drawing glyphs and every other tile marked as inside or outside the loop;
+//  with ansi the junk pipes are kept in grey on a coloured background, otherwise each tile is replaced by I or O
+pub fn render(input: &str, ansi: bool) -> String {
+    let (width, height, maze, path) = trace(input);
+    let mut on_loop = vec![false; width * height];
+    path.iter().for_each(|(x, y, _)| on_loop[(*y as usize) * width + (*x as usize)] = true);
+    let glyph = |pipe: u8| -> char {
+        match pipe {
+            p if p == NORTH | SOUTH => '│',
+            p if p == EAST | WEST => '─',
+            p if p == NORTH | EAST => '└',
+            p if p == NORTH | WEST => '┘',
+            p if p == SOUTH | WEST => '┐',
+            p if p == SOUTH | EAST => '┌',
+            _ => '·',
+        }
+    };
+
+    let mut output = String::new();
+    for y in 0..height {
+        // A tile is inside the loop when a ray going west crosses the loop an odd number of times, counting only the
+        //  loop pipes that connect north so that a run along the loop is crossed once or not at all
+        let mut inside = false;
+        for x in 0..width {
+            let index = y * width + x;
+            let pipe = maze[index];
+            if on_loop[index] {
+                if pipe & NORTH > 0 {
+                    inside = !inside;
+                }
+                output.push(glyph(pipe));
+            } else if ansi {
+                let background = if inside { 42 } else { 44 };
+                output.push_str(&format!("\x1b[90;{}m{}\x1b[0m", background, glyph(pipe)));
+            } else {
+                output.push(if inside { 'I' } else { 'O' });
             }
-        };
-        try_visit(x, y + 1);
-        try_visit(x, y - 1);
-        try_visit(x + 1, y);
-        try_visit(x - 1, y);
+        }
+        output.push('\n');
     }
-    return ((distance + 1) / 2, visit_count);
+    return output;
 }
 
 #[aoc(day10, part1)]
@@ -164,15 +183,11 @@ pub fn part1(input: &str) -> i32 {
 pub fn part2(input: &str) -> i32 {
     let (_, b) = solution(input);
     return b;
-    // 660 too high
-    // 471 apparently another answer
-    // 1xx too low
-
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2};
+    use super::{part1, part2, render};
 
     // part 1
     #[test]
@@ -196,6 +211,90 @@ FJL-7|--J|.
 L7..|||..|.
 .L--JL---J.
 ..........."), 14);
+        assert_eq!(part2("...........
+.S-------7.
+.|F-----7|.
+.||.....||.
+.||.....||.
+.|L-7.F-J|.
+.|..|.|..|.
+.L--J.L--J.
+..........."), 4);
+        // the same loop, now walked counter-clockwise as the start leaves to the north
+        assert_eq!(part2("...........
+.F-------7.
+.|F-----7|.
+.||.....||.
+.||.....||.
+.|L-7.F-J|.
+.|..|.|..|.
+.L--J.L--S.
+..........."), 4);
+        assert_eq!(part2(".F----7F7F7F7F-7....
+.|F--7||||||||FJ....
+.||.FJ||||||||L7....
+FJL7L7LJLJ||LJ.L-7..
+L--J.L7...LJS7F-7L7.
+....F-J..F7FJ|L7L7L7
+....L7.F7||L7|.L7L7|
+.....|FJLJ|FJ|F7|.LJ
+....FJL-7.||.||||...
+....L---J.LJ.LJLJ..."), 8);
+        assert_eq!(part2("FF7FSF7F7F7F7F7F---7
+L|LJ||||||||||||F--J
+FL-7LJLJ||||||LJL-77
+F--JF--7||LJLJ7F7FJ-
+L---JF-JLJ.||-FJLJJ7
+|F|F-JF---7F7-L7L|7|
+|FFJF7L7F-JF7|JL---7
+7-L-JL7||F7|L7F-7F7|
+L.L7LFJ|||||FJL7||LJ
+L7JLJL-JLJLJL--JLJ.L"), 10);
+    }
+
+    #[test]
+    fn rendering() {
+        let example = "..........
+.S------7.
+.|F----7|.
+.||....||.
+.||....||.
+.|L-7F-J|.
+.|..||..|.
+.L--JL--J.
+..........";
+        assert_eq!(render(example, false), "OOOOOOOOOO
+O┌──────┐O
+O│┌────┐│O
+O││OOOO││O
+O││OOOO││O
+O│└─┐┌─┘│O
+O│II││II│O
+O└──┘└──┘O
+OOOOOOOOOO
+");
+        let ansi = render(example, true);
+        assert_eq!(ansi.matches("\x1b[90;42m·\x1b[0m").count(), 4);
+        assert_eq!(ansi.matches("\x1b[90;44m·\x1b[0m").count(), 42);
+
+        // junk pipes are greyed out but still marked with the side of the loop they are on
+        let junk = render("-L|F7
+7S-7|
+L|7||
+-L-J|
+L|-JF", true);
+        assert!(junk.starts_with("\x1b[90;44m─\x1b[0m\x1b[90;44m└\x1b[0m"));
+        assert!(junk.contains("┌─┐"));
+        assert_eq!(render("-L|F7
+7S-7|
+L|7||
+-L-J|
+L|-JF", false), "OOOOO
+O┌─┐O
+O│I│O
+O└─┘O
+OOOOO
+");
     }
 
 }
@@ -11,49 +11,88 @@ impl Point {
     }
 }
 
-fn solution(input: &str, expansion: usize) -> usize {
-    let width = input.lines().next().unwrap().chars().count();
-    let height = input.lines().count();
-    let mut empty_cols = vec![true; width];
-    let mut empty_rows = vec![true; height];
-    let mut points: Vec<Point> = vec![];
-    input.lines().enumerate().for_each(|(y, line)| {
-        line.chars().enumerate().for_each(|(x, char)| {
-            match char {
-                '#' => {
+pub struct Universe {
+    galaxies: Vec<Point>,
+}
+
+impl Universe {
+    // Parses the image, growing every empty row by row_expansion and every empty column by column_expansion, which
+    //  must be at least 1 as an empty line cannot disappear
+    pub fn new(input: &str, row_expansion: usize, column_expansion: usize) -> Universe {
+        assert!(row_expansion >= 1, "row expansion must be at least 1, found {}", row_expansion);
+        assert!(column_expansion >= 1, "column expansion must be at least 1, found {}", column_expansion);
+        let width = input.lines().next().unwrap().chars().count();
+        let height = input.lines().count();
+        let mut empty_cols = vec![true; width];
+        let mut empty_rows = vec![true; height];
+        let mut points: Vec<Point> = vec![];
+        input.lines().enumerate().for_each(|(y, line)| {
+            line.chars().enumerate().for_each(|(x, char)| {
+                if char == '#' {
                     points.push(Point { x, y });
                     empty_cols[x] = false;
                     empty_rows[y] = false;
                 }
-                _ => {}
-            }
+            });
         });
-    });
-    fn before(empty: &Vec<bool>) -> Vec<usize> {
-        return empty.iter()
-            .fold((0, vec![]), |(mut count, mut array), is_empty| {
-                if *is_empty {
-                    count += 1;
-                }
-                array.push(count);
-                return (count, array);
-            }).1;
-    }
-    let empty_cols_before = before(&empty_cols);
-    let empty_rows_before = before(&empty_rows);
-    let points: Vec<Point> = points.iter().map(|point| Point {
-        x: point.x + (empty_cols_before[point.x] * (expansion - 1)),
-        y: point.y + (empty_rows_before[point.y] * (expansion - 1)),
-    }).collect();
-
-    let mut sum = 0;
-    for (i, a) in points.iter().enumerate() {
-        for b in points.as_slice()[i + 1..].iter() {
-            let distance = Point::distance(a, b);
-            sum += distance;
+        fn before(empty: &[bool]) -> Vec<usize> {
+            return empty.iter()
+                .scan(0, |count, is_empty| {
+                    if *is_empty {
+                        *count += 1;
+                    }
+                    return Some(*count);
+                }).collect();
         }
+        let empty_cols_before = before(&empty_cols);
+        let empty_rows_before = before(&empty_rows);
+        let galaxies: Vec<Point> = points.iter().map(|point| Point {
+            x: point.x + (empty_cols_before[point.x] * (column_expansion - 1)),
+            y: point.y + (empty_rows_before[point.y] * (row_expansion - 1)),
+        }).collect();
+        return Universe { galaxies };
+    }
+
+    pub fn len(&self) -> usize {
+        return self.galaxies.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.galaxies.is_empty();
+    }
+
+    // The expanded position of a galaxy as (x, y), galaxies are numbered from 1 in reading order like the puzzle does
+    pub fn galaxy(&self, number: usize) -> Option<(usize, usize)> {
+        let point = self.galaxies.get(number.checked_sub(1)?)?;
+        return Some((point.x, point.y));
+    }
+
+    pub fn distance(&self, a: usize, b: usize) -> Option<usize> {
+        let a = self.galaxies.get(a.checked_sub(1)?)?;
+        let b = self.galaxies.get(b.checked_sub(1)?)?;
+        return Some(Point::distance(a, b));
     }
-    return sum;
+
+    // The Manhattan distance splits per axis, so each axis is sorted and every coordinate contributes its distance to
+    //  all smaller coordinates at once using the sum of the coordinates before it
+    pub fn total_distance(&self) -> usize {
+        let axis_sum = |mut values: Vec<usize>| -> usize {
+            values.sort_unstable();
+            let mut prefix = 0;
+            let mut sum = 0;
+            for (i, value) in values.iter().enumerate() {
+                sum += value * i - prefix;
+                prefix += value;
+            }
+            return sum;
+        };
+        return axis_sum(self.galaxies.iter().map(|point| point.x).collect())
+            + axis_sum(self.galaxies.iter().map(|point| point.y).collect());
+    }
+}
+
+fn solution(input: &str, expansion: usize) -> usize {
+    return Universe::new(input, expansion, expansion).total_distance();
 }
 
 #[aoc(day11, part1)]
@@ -68,7 +107,7 @@ pub fn part2(input: &str) -> usize {
 
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2};
+    use super::{part1, part2, solution, Universe};
 
     // part 1
     #[test]
@@ -100,4 +139,45 @@ mod tests {
 #...#....."), 82000210);
     }
 
+    #[test]
+    fn universe() {
+        let example = "...#......
+.......#..
+#.........
+..........
+......#...
+.#........
+.........#
+..........
+.......#..
+#...#.....";
+        assert_eq!(solution(example, 10), 1030);
+        assert_eq!(solution(example, 100), 8410);
+
+        let universe = Universe::new(example, 2, 2);
+        assert_eq!(universe.len(), 9);
+        assert_eq!(universe.galaxy(1), Some((4, 0)));
+        assert_eq!(universe.distance(5, 9), Some(9));
+        assert_eq!(universe.distance(1, 7), Some(15));
+        assert_eq!(universe.distance(3, 6), Some(17));
+        assert_eq!(universe.distance(8, 9), Some(5));
+        assert_eq!(universe.distance(0, 9), None);
+        assert_eq!(universe.distance(1, 10), None);
+
+        // rows and columns expand independently, and the sorted sum matches summing every pair
+        let universe = Universe::new(example, 3, 7);
+        assert_eq!(universe.galaxy(1), Some((9, 0)));
+        assert_eq!(universe.galaxy(9), Some((10, 13)));
+        let pairs: usize = (1..=9).flat_map(|a| (a + 1..=9).map(move |b| (a, b)))
+            .map(|(a, b)| universe.distance(a, b).unwrap())
+            .sum();
+        assert_eq!(universe.total_distance(), pairs);
+    }
+
+    #[test]
+    #[should_panic(expected = "column expansion must be at least 1, found 0")]
+    fn no_expansion() {
+        Universe::new("#.\n..", 1, 0);
+    }
+
 }
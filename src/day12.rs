@@ -1,123 +1,166 @@
-use std::collections::HashMap;
+use std::cmp;
 
-fn can_place_at(row: &[char], index: usize, size: usize, exhaustive: bool) -> bool {
+fn can_place_at(row: &[u8], index: usize, size: usize) -> bool {
     // length must be sufficient
     let length = row.len();
     if index + size > length { return false; }
 
     // previous must be a possible boundary or begin
-    if index > 0 {
-        let previous = row.get(index - 1).unwrap();
-        if *previous == '#' { return false; }
-    }
+    if index > 0 && row[index - 1] == b'#' { return false; }
 
     // next must be a possible boundary or end
-    let next = row.get(index + size).unwrap_or(&'.');
-    if *next == '#' { return false; }
+    if row.get(index + size).is_some_and(|next| *next == b'#') { return false; }
 
-    // all in between must be possible
-    if !row.iter().skip(index).take(size).all(|v| *v == '?' || *v == '#') {
-        return false;
-    }
+    // finally, all in between must be possible
+    return row[index..index + size].iter().all(|v| *v == b'?' || *v == b'#');
+}
 
-    // ensure that the string has been exhausted (all #'s have been used)
-    if exhaustive {
-        return row.iter().skip(index + size).all(|v| *v != '#');
+// Builds the table where ways[group][index] is the number of arrangements of the groups from group onwards in the
+//  row from index onwards, filled from the end of the row so that each entry only looks at entries further along
+fn arrangement_table(row: &[u8], group_sizes: &[usize]) -> Vec<Vec<u128>> {
+    let length = row.len();
+    let groups = group_sizes.len();
+    let mut ways = vec![vec![0u128; length + 1]; groups + 1];
+    for group in (0..=groups).rev() {
+        ways[group][length] = if group == groups { 1 } else { 0 };
+        for index in (0..length).rev() {
+            let mut count = 0;
+            // leave this spring operational and continue with the next
+            if row[index] != b'#' {
+                count += ways[group][index + 1];
+            }
+            // or start the group here, which also uses up the operational spring that must follow it
+            if group < groups && can_place_at(row, index, group_sizes[group]) {
+                count += ways[group + 1][cmp::min(index + group_sizes[group] + 1, length)];
+            }
+            ways[group][index] = count;
+        }
     }
+    return ways;
+}
 
-    // finally, this placement is valid
-    return true;
+pub fn count_arrangements(row: &[u8], group_sizes: &[usize]) -> u128 {
+    return arrangement_table(row, group_sizes)[0][0];
 }
 
-fn find_placements(row: &[char], size: usize, exhaustive: bool) -> Vec<usize> {
-    let mut found = vec![];
-    for index in 0..row.len() {
-        if can_place_at(row, index, size, exhaustive) {
-            found.push(index);
-        }
-        // Stop after we have started with a hashtag,
-        //  as moving further would mean leaving a hashtag behind
-        if *row.get(index).unwrap() == '#' {
-            break;
+// Follows the table to the k-th arrangement in lexicographic order, where a # sorts before a . so at each spring the
+//  arrangements that start the next group there come before the ones that leave it operational
+fn unrank_with(ways: &[Vec<u128>], row: &[u8], group_sizes: &[usize], mut k: u128) -> Option<Vec<u8>> {
+    if k >= ways[0][0] {
+        return None;
+    }
+    let length = row.len();
+    let mut arrangement = Vec::with_capacity(length);
+    let mut group = 0;
+    let mut index = 0;
+    while index < length {
+        if group < group_sizes.len() && can_place_at(row, index, group_sizes[group]) {
+            let next = cmp::min(index + group_sizes[group] + 1, length);
+            let placed = ways[group + 1][next];
+            if k < placed {
+                arrangement.extend(std::iter::repeat_n(b'#', group_sizes[group]));
+                if next > index + group_sizes[group] {
+                    arrangement.push(b'.');
+                }
+                group += 1;
+                index = next;
+                continue;
+            }
+            k -= placed;
         }
+        arrangement.push(b'.');
+        index += 1;
     }
-    return found;
+    return Some(arrangement);
+}
+
+// Returns the k-th arrangement of the row, counting from 0 in lexicographic order
+pub fn unrank(row: &[u8], group_sizes: &[usize], k: u128) -> Option<Vec<u8>> {
+    return unrank_with(&arrangement_table(row, group_sizes), row, group_sizes, k);
 }
 
-fn find_arrangements(cache: &mut HashMap<String, usize>, row: &[char], group_sizes: &Vec<usize>) -> usize {
-    assert_ne!(group_sizes.len(), 0);
+pub struct Arrangements {
+    row: Vec<u8>,
+    group_sizes: Vec<usize>,
+    ways: Vec<Vec<u128>>,
+    rank: u128,
+}
+
+impl Iterator for Arrangements {
+    type Item = Vec<u8>;
 
-    // If this is the last group placement, then return the number of possible exhaustive placements
-    if group_sizes.len() == 1 {
-        return find_placements(row, group_sizes[0], true).len();
+    fn next(&mut self) -> Option<Vec<u8>> {
+        let arrangement = unrank_with(&self.ways, &self.row, &self.group_sizes, self.rank)?;
+        self.rank += 1;
+        return Some(arrangement);
     }
+}
 
-    // Otherwise recursively continue with the remaining row and groups from the different placements
-    let key = format!("{:?}/{}", group_sizes, row.iter().collect::<String>());
-    if cache.contains_key(&key) {
-        return *cache.get(&key).unwrap();
+// Lists every arrangement of the row in lexicographic order
+pub fn arrangements(row: &[u8], group_sizes: &[usize]) -> Arrangements {
+    return Arrangements {
+        row: row.to_vec(),
+        group_sizes: group_sizes.to_vec(),
+        ways: arrangement_table(row, group_sizes),
+        rank: 0,
+    };
+}
+
+// Picks an arrangement uniformly at random using a splitmix64 generator, advancing its state so that repeated calls
+//  give different arrangements, or None if the row has no arrangements
+pub fn sample(row: &[u8], group_sizes: &[usize], state: &mut u64) -> Option<Vec<u8>> {
+    let ways = arrangement_table(row, group_sizes);
+    let total = ways[0][0];
+    if total == 0 {
+        return None;
+    }
+    let mut next = || -> u64 {
+        *state = state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = *state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        return z ^ (z >> 31);
+    };
+    // Reject the values past the last multiple of the total so that every rank is equally likely
+    let limit = u128::MAX - u128::MAX % total;
+    loop {
+        let value = ((next() as u128) << 64) | next() as u128;
+        if value < limit {
+            return unrank_with(&ways, row, group_sizes, value % total);
+        }
     }
-    let val = find_placements(row, group_sizes[0], false).iter()
-        .map(|placement| {
-            let continue_from = *placement + group_sizes[0] + 1;
-            if continue_from >= row.len() {
-                return 0;
-            }
-            let remaining = row.len() - continue_from;
-            let remaining_groups: Vec<usize> = group_sizes.iter().map(|v| *v).skip(1).collect();
-            if remaining < (remaining_groups.len() - 1) + remaining_groups.iter().sum::<usize>() {
-                return 0;
-            }
-            return find_arrangements(
-                cache,
-                &row[continue_from..],
-                &remaining_groups,
-            )
-        })
-        .sum();
-    cache.insert(key, val);
-    val
 }
 
-#[aoc(day12, part1)]
-pub fn part1(input: &str) -> usize {
+// Parses a line into its row and group sizes, repeating both unfold times with the row copies joined by a ?
+pub fn parse(line: &str, unfold: usize) -> (Vec<u8>, Vec<usize>) {
+    let (row, groups) = line.split_once(' ').unwrap();
+    let group_sizes: Vec<usize> = groups.split(',').map(|v| v.parse().unwrap()).collect();
+    return (
+        vec![row; unfold].join("?").into_bytes(),
+        group_sizes.repeat(unfold),
+    );
+}
+
+pub fn solution(input: &str, unfold: usize) -> u128 {
     return input.lines().map(|line| {
-        let row: Vec<char> = line.split(' ').next().unwrap().chars().collect();
-        let mut cache = HashMap::new();
-        find_arrangements(
-            &mut cache,
-            &row[..],
-            &line.split(' ').skip(1).next().unwrap().split(',').map(|v| v.parse().unwrap()).collect()
-        )
+        let (row, group_sizes) = parse(line, unfold);
+        count_arrangements(&row, &group_sizes)
     }).sum();
 }
+
+#[aoc(day12, part1)]
+pub fn part1(input: &str) -> u128 {
+    return solution(input, 1);
+}
+
 #[aoc(day12, part2)]
-pub fn part2(input: &str) -> usize {
-    return input.lines().map(|line| {
-        let row_short = line.split(' ').next().unwrap();
-        let mut row = String::from(row_short);
-        let mut group_sizes: Vec<usize> = line.split(' ').skip(1).next().unwrap().split(',').map(|v| v.parse().unwrap()).collect();
-        let num_of_groups = group_sizes.len();
-        for _ in 0..4 {
-            row.push('?');
-            row.push_str(row_short);
-            for j in 0..num_of_groups {
-                group_sizes.push(group_sizes[j]);
-            }
-        }
-        let row: Vec<char> = row.chars().collect();
-        let mut cache = HashMap::new();
-        find_arrangements(
-            &mut cache,
-            &row[..],
-            &group_sizes,
-        )
-    }).sum();
+pub fn part2(input: &str) -> u128 {
+    return solution(input, 5);
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2};
+    use super::{arrangements, count_arrangements, parse, part1, part2, sample, solution, unrank};
 
     #[test]
     fn samples() {
@@ -136,4 +179,55 @@ mod tests {
         assert_eq!(part2(".?????????????. 1,2,1,1,1"), 1221405259893);
     }
 
+    #[test]
+    fn unfolding() {
+        assert_eq!(parse(".# 1", 5), (b".#?.#?.#?.#?.#".to_vec(), vec![1; 5]));
+        assert_eq!(solution("?###???????? 3,2,1", 1), 10);
+        assert_eq!(solution("?###???????? 3,2,1", 5), 506250);
+        assert_eq!(count_arrangements(b"", &[]), 1);
+        assert_eq!(count_arrangements(b"#", &[]), 0);
+        assert_eq!(count_arrangements(b"???", &[4]), 0);
+
+        // a single group of one in n unknown springs can be placed n ways, even when unfolded far past u64
+        let (row, group_sizes) = parse("?????????? 1", 20);
+        assert_eq!(row.len(), 219);
+        assert!(count_arrangements(&row, &group_sizes) > u64::MAX as u128);
+    }
+
+    #[test]
+    fn listing() {
+        let (row, group_sizes) = parse("?###???????? 3,2,1", 1);
+        let listed: Vec<String> = arrangements(&row, &group_sizes)
+            .map(|arrangement| String::from_utf8(arrangement).unwrap())
+            .collect();
+        assert_eq!(listed.len(), 10);
+        assert_eq!(listed[0], ".###.##.#...");
+        assert_eq!(listed[9], ".###....##.#");
+        assert!(listed.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(listed.iter().all(|arrangement| count_arrangements(arrangement.as_bytes(), &group_sizes) == 1));
+        assert_eq!(unrank(&row, &group_sizes, 4), Some(listed[4].clone().into_bytes()));
+        assert_eq!(unrank(&row, &group_sizes, 10), None);
+        assert_eq!(arrangements(b"???.###", &[1, 1, 3]).collect::<Vec<_>>(), vec![b"#.#.###".to_vec()]);
+        assert_eq!(arrangements(b"#", &[2]).count(), 0);
+
+        // sampling is deterministic for a seed and eventually reaches every arrangement
+        let mut state = 2023;
+        let first = sample(&row, &group_sizes, &mut state).unwrap();
+        assert_eq!(sample(&row, &group_sizes, &mut 2023), Some(first));
+        let mut seen = vec![false; listed.len()];
+        for _ in 0..200 {
+            let arrangement = String::from_utf8(sample(&row, &group_sizes, &mut state).unwrap()).unwrap();
+            seen[listed.iter().position(|listed| *listed == arrangement).unwrap()] = true;
+        }
+        assert!(seen.iter().all(|seen| *seen));
+        assert_eq!(sample(b"#", &[2], &mut state), None);
+
+        // unranking works on counts that do not fit in a u64
+        let (row, group_sizes) = parse("?????????? 1", 20);
+        let last = count_arrangements(&row, &group_sizes) - 1;
+        let arrangement = unrank(&row, &group_sizes, last).unwrap();
+        assert_eq!(arrangement.len(), row.len());
+        assert_eq!(count_arrangements(&arrangement, &group_sizes), 1);
+    }
+
 }
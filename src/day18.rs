@@ -1,4 +1,5 @@
 use std::cmp;
+use crate::polygon;
 
 #[derive(Debug, Copy, Clone)]
 pub enum Direction {
@@ -101,7 +102,7 @@ pub fn part1(input: &str) -> usize {
 }
 
 #[aoc(day18, part2)]
-pub fn part2(input: &str) -> f64 {
+pub fn part2(input: &str) -> i64 {
     let instructions: Vec<Instruction> = input.lines().map(|l| {
         let splits: Vec<&str> = l.split(' ').collect();
         let color = splits.get(2).unwrap();
@@ -118,29 +119,17 @@ pub fn part2(input: &str) -> f64 {
     }).collect();
     let mut x = 0;
     let mut y = 0;
-    let mut points: Vec<(f64, f64)> = vec![(x as f64, y as f64)];
+    let mut points: Vec<(i64, i64)> = vec![];
     instructions.iter().for_each(|instruction| {
         let (x_diff, y_diff) = get_diff(&instruction.direction);
-        x += x_diff * (instruction.meters as i32);
-        y += y_diff * (instruction.meters as i32);
-        points.push((x as f64, y as f64));
+        x += x_diff as i64 * (instruction.meters as i64);
+        y += y_diff as i64 * (instruction.meters as i64);
+        points.push((x, y));
     });
-    assert_eq!(points[0], points[points.len() - 1]);
+    assert_eq!(points[points.len() - 1], (0, 0));
 
-    // Calculate area of a polygon
-    let area = 0.5f64 * (0..(points.len() - 1)).map(|i| {
-        let (ax, ay) = points[i];
-        let (bx, by) = points[i + 1];
-        return ax * by - bx * ay;
-    }).sum::<f64>();
-    let circumference = 1f64 + (0..(points.len() - 1)).map(|i| {
-        let (ax, ay) = points[i];
-        let (bx, by) = points[i + 1];
-        let x_diff = ax - bx;
-        let y_diff = ay - by;
-        return (if x_diff < 0f64 {-x_diff} else {x_diff}) + (if y_diff < 0f64 {-y_diff} else {y_diff});
-    }).sum::<f64>() / 2f64;
-    return area + circumference;
+    // The lagoon is every grid point inside or on the edge of the dug polygon
+    return polygon::interior_points(&points) + polygon::boundary_points(&points);
 }
 
 #[cfg(test)]
@@ -164,6 +153,6 @@ U 3 (#a77fa3)
 L 2 (#015232)
 U 2 (#7a21e3)";
         assert_eq!(part1(example), 62);
-        assert_eq!(part2(example), 952408144115f64);
+        assert_eq!(part2(example), 952408144115);
     }
 }
@@ -1,4 +1,8 @@
-use std::collections::{HashMap, VecDeque};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::{Hash, Hasher};
+use crate::day8::lcm;
+use crate::graphviz::{Graph, Style};
 
 #[derive(Debug, Clone)]
 enum Variant {
@@ -13,193 +17,313 @@ struct Module {
     targets: Vec<String>,
 }
 
-#[aoc(day20, part1)]
-pub fn part1(input: &str) -> usize {
-    let mut pulses = VecDeque::<(bool, String, String)>::new();
-    let mut missing_targets_per_module = HashMap::<&str, Vec<&str>>::new();
-    let mut modules = HashMap::<String, Module>::new();
-    input.lines().for_each(|line| {
-        let splits: Vec<&str> = line.split(" -> ").collect();
-        let module = splits.get(0).unwrap();
-        let targets: Vec<&str> = splits.get(1).unwrap().split(", ").collect();
-        if *module == "broadcaster" {
-            targets.iter().for_each(|t| pulses.push_back((false, t.to_string(), module.to_string())));
-            return;
-        }
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pulse {
+    pub high: bool,
+    pub from: String,
+    pub to: String,
+}
 
-        // find the name of this module
-        let module_name = &module[1..];
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PulseCount {
+    pub low: usize,
+    pub high: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VcdStep {
+    Press,
+    Pulse,
+}
 
-        // insert all targets we missed before, as at that point this module didn't exist yet
-        let mut inputs = HashMap::new();
-        if let Some(missing_targets) = missing_targets_per_module.get(&module_name) {
-            missing_targets.iter().for_each(|missing_target| {
-                inputs.insert(missing_target.to_string(), false);
+#[derive(Debug, Clone)]
+pub struct Circuit {
+    broadcast: Vec<String>,
+    modules: HashMap<String, Module>,
+    initial: HashMap<String, Module>,
+    presses: usize,
+    sent: HashMap<String, PulseCount>,
+}
+
+impl Circuit {
+    pub fn new(input: &str) -> Circuit {
+        let mut broadcast = vec![];
+        let mut modules = HashMap::<String, Module>::new();
+        input.lines().for_each(|line| {
+            let splits: Vec<&str> = line.split(" -> ").collect();
+            let module = splits[0];
+            let targets: Vec<String> = splits[1].split(", ").map(|t| t.to_string()).collect();
+            if module == "broadcaster" {
+                broadcast = targets;
+                return;
+            }
+            let variant = if module.starts_with('%') { Variant::FlipFlop { status: false } } else { Variant::Conjunction };
+            modules.insert(module[1..].to_string(), Module {
+                inputs: HashMap::new(),
+                variant,
+                targets,
             });
-        }
-        missing_targets_per_module.remove(&module_name);
-
-        // construct the module
-        let variant = if module.starts_with('%') { Variant::FlipFlop { status: false } } else { Variant::Conjunction };
-        modules.insert(module_name.to_string(), Module {
-            inputs,
-            variant,
-            targets: targets.iter().map(|t| t.to_string()).collect(),
         });
 
-        // register each target
-        targets.iter().for_each(|target| {
-            if modules.contains_key(&target.to_string()) {
-                modules.get_mut(&target.to_string()).unwrap().inputs.insert(module_name.to_string(), false);
-            } else {
-                missing_targets_per_module.entry(target).or_default().push(&module_name);
-            }
-        })
-    });
-
-    // Draw graphviz
-    println!("--graphviz--\ndigraph G {{  broadcaster -> hd,xt,kj,zt;\n  broadcaster [color=green];\n  rx [color=green];\n");
-    modules.iter().for_each(|(k, v)| {
-        let color = match v.variant {
-            Variant::FlipFlop { .. } => "black",
-            Variant::Conjunction => "blue",
-        };
-        println!("  {} [color={}];{} -> {};", k, color, k, v.targets.join(","));
-    });
-    println!("}}\n--end graphviz--");
-    println!();
-
-    // Clusters
-    // xr: *hd,tp,rq,gr,zn,fh,xs,*sb,jh,bz,rg,dl
-    // tf: *xt,?cc,cr,?kk,fc,qx,?gq,jm,pj,dk,tb,kr
-    // gk: *zt,?xg,rd,cz,?vv,?br,fm,?dj,pd,kb,nt,ph
-    // gx: *kj,hs,?tk,?tt,gf,?sh,lr,?mz,rv,ck,nf,gd
-    let mut clusters = HashMap::new();
-    clusters.insert("xr", vec!["hd","tp","rq","gr","zn","fh","xs","sb","jh","bz","rg","dl"]);
-    clusters.insert("tf", vec!["xt","cc","cr","kk","fc","qx","gq","jm","pj","dk","tb","kr"]);
-    clusters.insert("gk", vec!["zt","xg","rd","cz","vv","br","fm","dj","pd","kb","nt","ph"]);
-    clusters.insert("gx", vec!["kj","hs","tk","tt","gf","sh","lr","mz","rv","ck","nf","gd"]);
-    clusters.iter_mut().for_each(|(_, parts)| parts.reverse());
-    let mut last_cluster_low_output = HashMap::new();
-
-    // Find pulses
-    let mut high_pulses_seen = 0;
-    let mut low_pulses_seen = 0;
-    for i in 0..5000 {
-        let mut pulses = pulses.clone();
-        low_pulses_seen += 1;
-        let mut last_pulse_from = HashMap::new();
-        let mut has_outputted_low = HashMap::new();
-        while let Some((pulse_in, to, from)) = pulses.pop_front() {
-            if i < 1000 {
-                if pulse_in { high_pulses_seen += 1; } else { low_pulses_seen += 1; }
-            }
-            let module = modules.get(to.as_str());
-            if module.is_none() {
-                continue;
-            }
-            let mut module = module.unwrap().clone();
-            if from != "broadcaster" {
-                *module.inputs.get_mut(from.as_str()).unwrap() = pulse_in;
+        // register each module as an input of its targets, now that all modules exist
+        let connections: Vec<(String, String)> = modules.iter()
+            .flat_map(|(name, module)| module.targets.iter().map(|target| (name.clone(), target.clone())))
+            .collect();
+        connections.into_iter().for_each(|(name, target)| {
+            if let Some(module) = modules.get_mut(&target) {
+                module.inputs.insert(name, false);
             }
-            let pulse_out = match module.variant {
-                Variant::FlipFlop { status } => {
-                    match pulse_in {
-                        false => {
+        });
+        return Circuit::from_modules(broadcast, modules);
+    }
+
+    fn from_modules(broadcast: Vec<String>, modules: HashMap<String, Module>) -> Circuit {
+        Circuit {
+            broadcast,
+            initial: modules.clone(),
+            modules,
+            presses: 0,
+            sent: HashMap::new(),
+        }
+    }
+
+    // presses the button once and returns every pulse in the order it was processed
+    pub fn press_button(&mut self) -> Vec<Pulse> {
+        self.presses += 1;
+        let mut log = vec![];
+        let mut pulses = VecDeque::from([Pulse { high: false, from: String::from("button"), to: String::from("broadcaster") }]);
+        while let Some(pulse) = pulses.pop_front() {
+            let count = self.sent.entry(pulse.from.clone()).or_default();
+            if pulse.high { count.high += 1; } else { count.low += 1; }
+            let targets = if pulse.to == "broadcaster" {
+                Some((false, &self.broadcast))
+            } else if let Some(module) = self.modules.get_mut(pulse.to.as_str()) {
+                if let Some(input) = module.inputs.get_mut(pulse.from.as_str()) {
+                    *input = pulse.high;
+                }
+                match module.variant {
+                    Variant::FlipFlop { status } => {
+                        if pulse.high {
+                            None
+                        } else {
                             module.variant = Variant::FlipFlop { status: !status };
-                            Some(!status)
+                            Some((!status, &module.targets))
                         }
-                        true => { None }
                     }
+                    Variant::Conjunction => Some((!module.inputs.values().all(|high| *high), &module.targets)),
                 }
-                Variant::Conjunction => { Some(!module.inputs.values().all(|pulse| *pulse)) }
+            } else {
+                None
             };
-            if pulse_out.is_none() {
-                last_pulse_from.remove(to.as_str());
-                continue;
-            }
-            last_pulse_from.insert(to.clone(), pulse_out.unwrap());
-            if !pulse_out.unwrap() {
-                has_outputted_low.insert(to.clone(), true);
-            }
-            for next_target in module.targets.clone() {
-                pulses.push_back((pulse_out.unwrap(), next_target, to.clone()));
+            if let Some((high, targets)) = targets {
+                for target in targets.iter() {
+                    pulses.push_back(Pulse { high, from: pulse.to.clone(), to: target.clone() });
+                }
             }
-            modules.insert(to, module);
+            log.push(pulse);
         }
+        return log;
+    }
 
-        // visualize clusters
-        // clusters.iter().for_each(|(cluster, parts)| {
-        //     println!("{}: {}", cluster, parts.iter().map(|part| {
-        //         if let Some(val) = last_pulse_from.get(*part) {
-        //             if !*val {
-        //                 return "1";
-        //             };
-        //             return "0";
-        //         }
-        //         return "_";
-        //     }).collect::<String>());
-        // });
-        clusters.iter().for_each(|(cluster, parts)| {
-            if has_outputted_low.get(*cluster).is_none() {
-                return;
+    // presses the button until the module sends a pulse of the given level, returning the number of presses it took,
+    //  or None if it did not happen within max_presses
+    pub fn presses_until(&mut self, module: &str, high: bool, max_presses: usize) -> Option<usize> {
+        return (1..=max_presses).find(|_| self.press_button().iter().any(|pulse| pulse.high == high && pulse.from == module));
+    }
+
+    // returns a hash of the memory of all modules, which is equal for equal states
+    pub fn state_hash(&self) -> u64 {
+        let mut names: Vec<&String> = self.modules.keys().collect();
+        names.sort();
+        let mut hasher = DefaultHasher::new();
+        names.iter().for_each(|name| {
+            let module = self.modules.get(*name).unwrap();
+            name.hash(&mut hasher);
+            match module.variant {
+                Variant::FlipFlop { status } => status.hash(&mut hasher),
+                Variant::Conjunction => {
+                    let mut inputs: Vec<(&String, &bool)> = module.inputs.iter().collect();
+                    inputs.sort();
+                    inputs.hash(&mut hasher);
+                }
             }
-            let cluster_module = modules.get(*cluster);
-            if cluster_module.is_none() {
-                return;
+        });
+        return hasher.finish();
+    }
+
+    // puts all modules back in their initial state and clears the counters
+    pub fn reset(&mut self) {
+        self.modules = self.initial.clone();
+        self.presses = 0;
+        self.sent.clear();
+    }
+
+    pub fn presses(&self) -> usize {
+        return self.presses;
+    }
+
+    // returns the number of low and high pulses the module has sent since the last reset
+    pub fn pulses_sent(&self, module: &str) -> PulseCount {
+        return self.sent.get(module).copied().unwrap_or_default();
+    }
+
+    // returns the total number of low and high pulses sent since the last reset
+    pub fn total_pulses_sent(&self) -> PulseCount {
+        return self.sent.values().fold(PulseCount::default(), |acc, count| PulseCount {
+            low: acc.low + count.low,
+            high: acc.high + count.high,
+        });
+    }
+
+    // returns a circuit with only the modules upstream of the given module, in their initial state, without following
+    //  the inputs past the stop module so that a loop back through it does not pull it into the circuit
+    pub fn upstream_of(&self, module: &str, stop: Option<&str>) -> Circuit {
+        let mut upstream = HashSet::new();
+        let mut open = vec![module];
+        while let Some(current) = open.pop() {
+            if Some(current) == stop || !upstream.insert(current) {
+                continue;
+            }
+            if let Some(module) = self.initial.get(current) {
+                module.inputs.keys().for_each(|input| open.push(input.as_str()));
             }
-            let cluster_module = cluster_module.unwrap();
-            let inputs = cluster_module.inputs.clone();
-            let diff = last_cluster_low_output.get(*cluster).map(|last| i - last);
-            println!("{}: {} ({:?} from {} since last {:?})", cluster, parts.iter().filter_map(|v| {
-                let i = inputs.get(*v);
-                if i.is_none() {
-                    return None;
+        }
+        let broadcast = self.broadcast.iter().filter(|t| upstream.contains(t.as_str())).cloned().collect();
+        let modules = self.initial.iter()
+            .filter(|(name, _)| upstream.contains(name.as_str()))
+            .map(|(name, module)| (name.clone(), module.clone()))
+            .collect();
+        return Circuit::from_modules(broadcast, modules);
+    }
+
+    // presses the button the given number of times and returns a VCD waveform of the output of each module, with a
+    //  time step for each press or for each pulse
+    pub fn to_vcd(&mut self, presses: usize, step: VcdStep) -> String {
+        let mut names: Vec<String> = self.modules.keys().cloned().collect();
+        names.push(String::from("broadcaster"));
+        names.sort();
+
+        // identifiers are made from the printable ascii characters
+        let identifier = |mut index: usize| -> String {
+            let mut id = String::new();
+            loop {
+                id.push((b'!' + (index % 94) as u8) as char);
+                index /= 94;
+                if index == 0 {
+                    return id;
                 }
-                return Some(if *i.unwrap() {"1"} else {"0"});
-            }).collect::<String>(), has_outputted_low.get(*cluster), i, diff);
-            last_cluster_low_output.insert(*cluster, i);
-        });
+                index -= 1;
+            }
+        };
+        let identifiers: HashMap<&String, String> = names.iter().enumerate().map(|(index, name)| (name, identifier(index))).collect();
 
-        // visualize cluster outputs
-        clusters.iter().for_each(|(cluster, _)| {
-            if let Some(val) = last_pulse_from.get(*cluster) {
-                if !*val { println!("{}: {}", cluster, i); }
+        let mut vcd = String::from("$timescale 1ns $end\n$scope module circuit $end\n");
+        names.iter().for_each(|name| vcd.push_str(&format!("$var wire 1 {} {} $end\n", identifiers[name], name)));
+        vcd.push_str("$upscope $end\n$enddefinitions $end\n#0\n$dumpvars\n");
+        let mut outputs: HashMap<&String, bool> = names.iter().map(|name| (name, false)).collect();
+        names.iter().for_each(|name| vcd.push_str(&format!("0{}\n", identifiers[name])));
+        vcd.push_str("$end\n");
+
+        let mut time = 0;
+        for _ in 0..presses {
+            let before = outputs.clone();
+            for pulse in self.press_button() {
+                let name = match names.iter().find(|name| **name == pulse.from) {
+                    Some(name) => name,
+                    None => continue,
+                };
+                outputs.insert(name, pulse.high);
+                if step == VcdStep::Pulse {
+                    time += 1;
+                    vcd.push_str(&format!("#{}\n{}{}\n", time, if pulse.high { 1 } else { 0 }, identifiers[name]));
+                }
             }
-        });
-        if clusters.iter().all(|(cluster, _)| {
-            if let Some(val) = last_pulse_from.get(*cluster) {
-                return !*val;
+
+            // when stepping per press, only the outputs that differ from the previous press are written
+            if step == VcdStep::Press {
+                time += 1;
+                vcd.push_str(&format!("#{}\n", time));
+                names.iter()
+                    .filter(|name| outputs[name] != before[name])
+                    .for_each(|name| vcd.push_str(&format!("{}{}\n", if outputs[name] { 1 } else { 0 }, identifiers[name])));
             }
-            return false;
-        }) {
-            println!("clusters: all found at {}", i);
         }
+        return vcd;
+    }
+
+    // renders the modules as a graphviz graph, with flip-flops as boxes and conjunctions in blue
+    pub fn to_dot(&self) -> String {
+        let mut names: Vec<&String> = self.initial.keys().collect();
+        names.sort();
+        let mut graph = Graph::directed();
+        graph.node("broadcaster", None, Style::color("green"));
+        names.iter().for_each(|name| {
+            let style = match self.initial.get(*name).unwrap().variant {
+                Variant::FlipFlop { .. } => Style::default().with_shape("box"),
+                Variant::Conjunction => Style::color("blue"),
+            };
+            graph.node(name, None, style);
+        });
+
+        // targets that are not a module, such as rx, only receive pulses
+        let mut sinks: Vec<&String> = self.initial.values()
+            .flat_map(|module| module.targets.iter())
+            .filter(|target| !self.initial.contains_key(*target))
+            .collect();
+        sinks.sort();
+        sinks.dedup();
+        sinks.iter().for_each(|sink| { graph.node(sink, None, Style::color("red")); });
+
+        self.broadcast.iter().for_each(|target| { graph.edge("broadcaster", target, None); });
+        names.iter().for_each(|name| {
+            self.initial.get(*name).unwrap().targets.iter().for_each(|target| { graph.edge(name, target, None); });
+        });
+        return graph.to_dot();
     }
-    return low_pulses_seen * high_pulses_seen;
+}
+
+#[aoc(day20, part1)]
+pub fn part1(input: &str) -> usize {
+    let mut circuit = Circuit::new(input);
+    for _ in 0..1000 {
+        circuit.press_button();
+    }
+    let total = circuit.total_pulses_sent();
+    return total.low * total.high;
 }
 
 #[aoc(day20, part2)]
-pub fn part2(_input: &str) -> usize {
-    // solution -- see graphviz from part 1
-
-    // graph clusters
-    // xr: *hd,tp,rq,gr,zn,fh,xs,*sb,jh,bz,rg,dl
-    // tf: *xt,?cc,cr,?kk,fc,qx,?gq,jm,pj,dk,tb,kr
-    // gk: *zt,?xg,rd,cz,?vv,?br,fm,?dj,pd,kb,nt,ph
-    // gx: *kj,hs,?tk,?tt,gf,?sh,lr,?mz,rv,ck,nf,gd
-
-    // answers
-    // each cluster cycles in the below intervals
-    return 4021 * 3917 * 3923 * 3967;
-    // too low:        479001600
-    // correct:  245114020323037
-    // too high: 281474980000000
+pub fn part2(input: &str) -> u64 {
+    let circuit = Circuit::new(input);
+
+    // rx only receives a low pulse when the conjunction feeding it has seen a high pulse from all of its inputs
+    let feeders: Vec<&String> = circuit.modules.iter()
+        .filter(|(_, module)| module.targets.iter().any(|target| target == "rx"))
+        .map(|(name, _)| name)
+        .collect();
+    let feeder = match feeders[..] {
+        [feeder] => feeder,
+        _ => panic!("expected exactly one module to feed rx, found {:?}", feeders),
+    };
+    let feeder_module = circuit.modules.get(feeder).unwrap();
+    if !matches!(feeder_module.variant, Variant::Conjunction) {
+        panic!("expected {} that feeds rx to be a conjunction", feeder);
+    }
+
+    // each input of the feeder is driven by its own sub-counter, find the period of each of them in isolation
+    let max_presses = 1 << 20;
+    let periods: Vec<u64> = feeder_module.inputs.keys()
+        .map(|output| {
+            circuit.upstream_of(output, Some(feeder)).presses_until(output, true, max_presses)
+                .unwrap_or_else(|| panic!("{} did not send a high pulse within {} presses", output, max_presses)) as u64
+        })
+        .collect();
+    return lcm(&periods);
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2};
+    use super::{Circuit, Pulse, VcdStep, part1, part2};
 
     #[test]
     fn samples() {
@@ -215,6 +339,124 @@ mod tests {
 &con -> output";
         assert_eq!(part1(example1), 32000000);
         assert_eq!(part1(example2), 11687500);
-        assert_eq!(part2(example1), 245114020323037);
+    }
+
+    #[test]
+    fn circuit() {
+        let mut circuit = Circuit::new("broadcaster -> a
+%a -> inv, con
+&inv -> b
+%b -> con
+&con -> output");
+        let initial_hash = circuit.state_hash();
+        let pulse = |high: bool, from: &str, to: &str| Pulse { high, from: from.to_string(), to: to.to_string() };
+        assert_eq!(circuit.press_button(), vec![
+            pulse(false, "button", "broadcaster"),
+            pulse(false, "broadcaster", "a"),
+            pulse(true, "a", "inv"),
+            pulse(true, "a", "con"),
+            pulse(false, "inv", "b"),
+            pulse(true, "con", "output"),
+            pulse(true, "b", "con"),
+            pulse(false, "con", "output"),
+        ]);
+        assert_ne!(circuit.state_hash(), initial_hash);
+        assert_eq!(circuit.pulses_sent("con").low, 1);
+        assert_eq!(circuit.pulses_sent("con").high, 1);
+
+        // the circuit returns to its initial state after four presses
+        circuit.press_button();
+        circuit.press_button();
+        circuit.press_button();
+        assert_eq!(circuit.state_hash(), initial_hash);
+        assert_eq!(circuit.presses(), 4);
+
+        circuit.reset();
+        assert_eq!(circuit.presses(), 0);
+        assert_eq!(circuit.total_pulses_sent().low, 0);
+        assert_eq!(circuit.presses_until("b", false, 10), Some(3));
+        assert_eq!(circuit.presses_until("output", false, 10), None);
+        assert_eq!(circuit.presses(), 13);
+
+        // the sub-counter of a loops back through the feeder, which stays outside of the isolated circuit
+        let circuit = Circuit::new("broadcaster -> a
+%a -> c
+&c -> feed
+&feed -> rx, c");
+        let mut names: Vec<String> = circuit.upstream_of("c", Some("feed")).modules.into_keys().collect();
+        names.sort();
+        assert_eq!(names, vec!["a", "c"]);
+        assert_eq!(circuit.upstream_of("c", None).modules.len(), 3);
+    }
+
+    #[test]
+    fn dot() {
+        let circuit = Circuit::new("broadcaster -> a
+%a -> inv, con
+&inv -> b
+%b -> con
+&con -> output");
+        assert_eq!(circuit.to_dot(), "digraph G {
+  broadcaster [color=green];
+  a [shape=box];
+  b [shape=box];
+  con [color=blue];
+  inv [color=blue];
+  output [color=red];
+  broadcaster -> a;
+  a -> inv;
+  a -> con;
+  b -> con;
+  con -> output;
+  inv -> b;
+}
+");
+    }
+
+    #[test]
+    fn vcd() {
+        let mut circuit = Circuit::new("broadcaster -> a
+%a -> inv, con
+&inv -> b
+%b -> con
+&con -> output");
+        let header = "$timescale 1ns $end
+$scope module circuit $end
+$var wire 1 ! a $end
+$var wire 1 \" b $end
+$var wire 1 # broadcaster $end
+$var wire 1 $ con $end
+$var wire 1 % inv $end
+$upscope $end
+$enddefinitions $end
+#0
+$dumpvars
+0!
+0\"
+0#
+0$
+0%
+$end
+";
+        assert_eq!(circuit.to_vcd(2, VcdStep::Press), format!("{}#1\n1!\n1\"\n#2\n0!\n1$\n1%\n", header));
+        circuit.reset();
+        assert_eq!(circuit.to_vcd(1, VcdStep::Pulse), format!("{}#1\n0#\n#2\n1!\n#3\n1!\n#4\n0%\n#5\n1$\n#6\n1\"\n#7\n0$\n", header));
+    }
+
+    #[test]
+    fn counters() {
+        // two binary counters that reset at 5 (0b101) and 3 (0b11) presses
+        let example = "broadcaster -> a0, b0
+%a0 -> a1, ac
+%a1 -> a2
+%a2 -> ac
+&ac -> a0, a1, ai
+&ai -> fd
+%b0 -> b1, bc
+%b1 -> bc
+&bc -> b0, bi
+&bi -> fd
+&fd -> rx";
+        assert_eq!(part2(example), 15);
     }
 }
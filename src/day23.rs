@@ -1,5 +1,6 @@
 use std::cmp;
 use std::collections::HashMap;
+use crate::graphviz::{Graph, Style};
 
 #[derive(PartialEq)]
 enum Tile {
@@ -144,21 +145,30 @@ fn find_sections(input: &str) -> (usize, HashMap<usize, Section>) {
         });
     }
 
-    // Visualize the sections using graphviz
-    println!("--graphviz sections--");
-    println!("digraph G {{\n  begin -> {}", begin);
-    sections.iter().for_each(|(k, v)| {
-        if v.next.is_empty() {
-            print!("  {} -> goal;", k);
-        } else {
-            print!("  {} -> {};", k, v.next.iter().map(|v| v.to_string()).collect::<Vec<String>>().join(","));
+    (begin, sections)
+}
+
+// renders the sections of the trail as a graphviz graph, where each slide is an edge
+pub fn to_dot(input: &str) -> String {
+    let (begin, sections) = find_sections(input);
+    let mut starts: Vec<&usize> = sections.keys().collect();
+    starts.sort();
+    let mut graph = Graph::directed();
+    graph.node("begin", None, Style::color("green"));
+    graph.node("goal", None, Style::color("red"));
+    starts.iter().for_each(|start| {
+        let section = sections.get(*start).unwrap();
+        graph.node(&start.to_string(), Some(format!("length {}", section.length)), Style::default().with_shape("box"));
+    });
+    graph.edge("begin", &begin.to_string(), None);
+    starts.iter().for_each(|start| {
+        let section = sections.get(*start).unwrap();
+        if section.next.is_empty() {
+            graph.edge(&start.to_string(), "goal", None);
         }
-        println!("  {} [label=\"length {}\"];", k, v.length);
+        section.next.iter().for_each(|next| { graph.edge(&start.to_string(), &next.to_string(), None); });
     });
-    println!("}}");
-    println!("--end graphviz sections--");
-
-    (begin, sections)
+    return graph.to_dot();
 }
 
 #[aoc(day23, part1)]
@@ -248,7 +258,7 @@ pub fn part2(input: &str) -> usize {
 
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2};
+    use super::{part1, part2, to_dot};
 
     #[test]
     fn samples() {
@@ -277,5 +287,10 @@ mod tests {
 #####################.#";
         assert_eq!(part1(example), 94);
         assert_eq!(part2(example), 154);
+
+        let dot = to_dot(example);
+        assert!(dot.starts_with("digraph G {\n  begin [color=green];\n  goal [color=red];\n  1 [label=\"length 14\", shape=box];\n"));
+        assert!(dot.contains("  begin -> 1;\n"));
+        assert_eq!(dot.matches(" -> goal;").count(), 1);
     }
 }
@@ -1,16 +1,16 @@
-struct Line {
-    x: f64,
-    y: f64,
-    z: f64,
-    vx: f64,
-    vy: f64,
-    vz: f64,
+pub struct Line {
+    x: i64,
+    y: i64,
+    z: i64,
+    vx: i64,
+    vy: i64,
+    vz: i64,
 }
 
 impl Line {
-    fn new(text: &str) -> Line {
+    pub fn new(text: &str) -> Line {
         let splits = text.split(" @ ").collect::<Vec<&str>>();
-        let get_xyz = |split_index: usize| -> (f64, f64, f64) {
+        let get_xyz = |split_index: usize| -> (i64, i64, i64) {
             match splits.get(split_index).unwrap().split(", ").collect::<Vec<&str>>()[..] {
                 [x, y, z] => (x.trim().parse().unwrap(), y.trim().parse().unwrap(), z.trim().parse().unwrap()),
                 _ => panic!("cannot get xyz from '{}'", splits.get(split_index).unwrap()),
@@ -21,112 +21,294 @@ impl Line {
         Line { x, y, z, vx, vy, vz }
     }
 
-    fn find_xy_intersection(&self, other: &Line) -> Option<(f64, f64)> {
+    fn position(&self) -> Vector {
+        [self.x as i128, self.y as i128, self.z as i128]
+    }
+
+    fn velocity(&self) -> Vector {
+        [self.vx as i128, self.vy as i128, self.vz as i128]
+    }
+
+    fn find_xy_intersection(&self, other: &Line) -> Option<(/*point*/ (f64, f64), /*times*/ (f64, f64))> {
+        let (x, y, vx, vy) = (self.x as f64, self.y as f64, self.vx as f64, self.vy as f64);
+        let (other_x, other_y, other_vx, other_vy) = (other.x as f64, other.y as f64, other.vx as f64, other.vy as f64);
+
         // lines are parallel (skip collinear case)
-        let s = self.vx * other.vy - self.vy * other.vx;
+        let s = vx * other_vy - vy * other_vx;
         if s == 0f64 {
             return None;
         }
 
-        let diff = (other.x - self.x, other.y - self.y);
-        let t = (diff.0 * other.vy - diff.1 * other.vx) / s;
-        let u = (diff.0 * self.vy - diff.1 * self.vx) / s;
+        let diff = (other_x - x, other_y - y);
+        let t = (diff.0 * other_vy - diff.1 * other_vx) / s;
+        let u = (diff.0 * vy - diff.1 * vx) / s;
 
         // intersection happened prior to either line
         if t < 0f64 || u < 0f64 {
             return None;
         }
-        return Some((self.x + t * self.vx, self.y + t * self.vy));
+        return Some(((x + t * vx, y + t * vy), (t, u)));
     }
 
-    fn find_xyz_intersection(&self, other: &Line) -> Option<(f64, f64, f64)> {
-        // no intersection as lines are parallel (skip collinear case)
-        let s = self.vx * other.vy - self.vy * other.vx;
-        if s == 0f64 {
-            return None;
+    // finds where the future paths of two hailstones cross in 3d, which does not have to be at the same time
+    pub fn find_xyz_intersection(&self, other: &Line) -> Intersection {
+        let diff = sub(other.position(), self.position());
+        let normal = cross(self.velocity(), other.velocity()).unwrap();
+
+        // parallel paths either never meet, or lie on the same line where the future paths overlap unless the
+        //  hailstones move away from each other
+        if normal == [0, 0, 0] {
+            if cross(diff, self.velocity()).unwrap() != [0, 0, 0] {
+                return Intersection::None;
+            }
+            let other_is_ahead = dot(diff, self.velocity()) >= 0;
+            if dot(self.velocity(), other.velocity()) > 0 {
+                let start = if other_is_ahead { other.position() } else { self.position() };
+                return Intersection::Collinear { start, end: None };
+            }
+            if !other_is_ahead {
+                return Intersection::None;
+            }
+            return Intersection::Collinear { start: self.position(), end: Some(other.position()) };
         }
 
-        // calculations
-        let diff = (other.x - self.x, other.y - self.y);
-        let t = (diff.0 * other.vy - diff.1 * other.vx) / s;
-        let u = (diff.0 * self.vy - diff.1 * self.vx) / s;
+        // skew paths never meet
+        if dot(diff, normal) != 0 {
+            return Intersection::None;
+        }
 
-        // no intersection as intersection would have happened prior to either line
-        if t < 0f64 || u < 0f64 {
-            return None;
+        // solve self + t * v_self = other + u * v_other within the plane both paths lie in
+        let length_squared = dot(normal, normal);
+        let t = Fraction::new(dot(cross(diff, other.velocity()).unwrap(), normal), length_squared);
+        let u = Fraction::new(dot(cross(diff, self.velocity()).unwrap(), normal), length_squared);
+        if t.numerator < 0 || u.numerator < 0 {
+            return Intersection::None;
         }
+        let (p, v) = (self.position(), self.velocity());
+        let point = [0, 1, 2].map(|i| Fraction::new(p[i] * t.denominator + v[i] * t.numerator, t.denominator));
+        return Intersection::Point { point, times: (t, u) };
+    }
 
-        // no intersection as intersection didn't happen in z coordinate
-        let z_self = self.z + t * self.vz;
-        let z_other = other.z + u * other.vz;
-        if z_self != z_other {
-            return None;
+    // finds the moment from now on at which two hailstones are closest to each other, and the squared distance then
+    pub fn closest_approach(&self, other: &Line) -> Approach {
+        let diff = sub(other.position(), self.position());
+        let closing = sub(other.velocity(), self.velocity());
+
+        // the distance is |diff + closing * t|, which is smallest at t = -(diff . closing) / (closing . closing)
+        let speed_squared = dot(closing, closing);
+        let projection = dot(diff, closing);
+        if speed_squared == 0 || projection >= 0 {
+            return Approach { time: Fraction::new(0, 1), distance_squared: Fraction::new(dot(diff, diff), 1) };
         }
+        let perpendicular = cross(diff, closing).unwrap();
+        return Approach {
+            time: Fraction::new(-projection, speed_squared),
+            distance_squared: Fraction::new(dot(perpendicular, perpendicular), speed_squared),
+        };
+    }
 
-        // return intersection point
-        return Some((self.x + t * self.vx, self.y + t * self.vy, self.z + t * self.vz));
+    pub fn min_distance(&self, other: &Line) -> f64 {
+        return self.closest_approach(other).distance_squared.to_f64().sqrt();
     }
+}
 
-    fn with_velocity(&self, dvx: f64, dvy: f64, dvz: f64) -> Line {
-        Line {
-            x: self.x,
-            y: self.y,
-            z: self.z,
-            vx: self.vx + dvx,
-            vy: self.vy + dvy,
-            vz: self.vz + dvz,
-        }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fraction {
+    pub numerator: i128,
+    pub denominator: i128,
+}
+
+impl Fraction {
+    pub fn new(numerator: i128, denominator: i128) -> Fraction {
+        assert_ne!(denominator, 0);
+        let divisor = gcd(numerator, denominator) * denominator.signum();
+        Fraction { numerator: numerator / divisor, denominator: denominator / divisor }
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        return self.numerator as f64 / self.denominator as f64;
     }
 }
 
-#[aoc(day24, part1)]
-pub fn part1(input: &str) -> usize {
-    let lines: Vec<Line> = input.lines().map(Line::new).collect();
-    let boundary: (f64, f64) = if lines.len() == 5 {(7f64, 27f64)} else {(200000000000000f64, 400000000000000f64)};
-    let mut intersections = 0;
-    for i in 0..lines.len() {
-        for j in (i + 1)..lines.len() {
-            let intersection = lines[i].find_xy_intersection(&lines[j]);
-            if let Some(point) = intersection  {
-                if point.0 >= boundary.0 && point.0 <= boundary.1
-                    && point.1 >= boundary.0 && point.1 <= boundary.1 {
-                    intersections += 1;
+#[derive(Debug, PartialEq, Eq)]
+pub enum Intersection {
+    None,
+    Point { point: [Fraction; 3], times: (Fraction, Fraction) },
+    // the future paths overlap from the start, until the end if the hailstones move towards each other
+    Collinear { start: [i128; 3], end: Option<[i128; 3]> },
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Approach {
+    pub time: Fraction,
+    pub distance_squared: Fraction,
+}
+
+type Vector = [i128; 3];
+
+fn sub(a: Vector, b: Vector) -> Vector {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn cross(a: Vector, b: Vector) -> Option<Vector> {
+    let component = |i: usize, j: usize| a[i].checked_mul(b[j])?.checked_sub(a[j].checked_mul(b[i])?);
+    Some([component(1, 2)?, component(2, 0)?, component(0, 1)?])
+}
+
+fn dot(a: Vector, b: Vector) -> i128 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    if b == 0 {
+        return a.abs();
+    }
+    gcd(b, a % b)
+}
+
+// divides a vector by the gcd of its components, keeping its direction
+fn reduce(v: Vector) -> Vector {
+    let divisor = gcd(gcd(v[0], v[1]), v[2]);
+    if divisor == 0 {
+        return v;
+    }
+    [v[0] / divisor, v[1] / divisor, v[2] / divisor]
+}
+
+// finds the time t for which offset + velocity * t is zero, if there is a single exact integer time
+fn solve_time(offset: Vector, velocity: Vector) -> Option<i128> {
+    let component = (0..3).find(|i| velocity[*i] != 0)?;
+    if offset[component] % velocity[component] != 0 {
+        return None;
+    }
+    let t = -offset[component] / velocity[component];
+    if (0..3).all(|i| offset[i] + velocity[i] * t == 0) { Some(t) } else { None }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Rock {
+    pub position: (i128, i128, i128),
+    pub velocity: (i128, i128, i128),
+    pub collision_times: Vec<i128>,
+}
+
+// Finds the rock that collides with every hailstone. In the frame of reference of the first hailstone the rock passes
+//  through the origin, so it lies in the plane through the origin and any other hailstone. The intersection of two
+//  such planes gives the direction of the rock, from which the collision times with those two hailstones follow.
+pub fn find_rock(hailstones: &[Line]) -> Option<Rock> {
+    let origin = hailstones.first()?;
+    let relative: Vec<(Vector, Vector)> = hailstones.iter()
+        .map(|h| (sub(h.position(), origin.position()), sub(h.velocity(), origin.velocity())))
+        .collect();
+    let normals: Vec<Option<Vector>> = relative.iter()
+        .map(|(p, v)| cross(*p, *v).map(reduce))
+        .collect();
+
+    // find the direction of the rock, relative to the first hailstone, from two planes that are not the same
+    let mut direction = None;
+    'search: for i in 1..hailstones.len() {
+        for j in (i + 1)..hailstones.len() {
+            if let (Some(a), Some(b)) = (normals[i], normals[j]) {
+                if let Some(d) = cross(a, b) {
+                    if d != [0, 0, 0] {
+                        direction = Some(reduce(d));
+                        break 'search;
+                    }
                 }
             }
         }
     }
-    return intersections;
+    let direction = direction?;
+
+    // the rock hits hailstone i at time t when its relative position is parallel to the direction of the rock
+    let times: Vec<(usize, i128)> = (1..hailstones.len()).filter_map(|i| {
+        let (p, v) = relative[i];
+        let numerator = cross(p, direction)?;
+        let denominator = cross(v, direction)?;
+        let k = (0..3).find(|k| denominator[*k] != 0)?;
+        if numerator[k] % denominator[k] != 0 {
+            return None;
+        }
+        Some((i, -numerator[k] / denominator[k]))
+    }).collect();
+    let (&(i, ti), &(j, tj)) = times.iter().zip(times.iter().skip(1)).find(|(a, b)| a.1 != b.1)?;
+
+    // from two collisions at different times the position and velocity of the rock follow
+    let hit = |index: usize, t: i128| {
+        let (p, v) = (hailstones[index].position(), hailstones[index].velocity());
+        [p[0] + v[0] * t, p[1] + v[1] * t, p[2] + v[2] * t]
+    };
+    let (hit_i, hit_j) = (hit(i, ti), hit(j, tj));
+    let mut velocity = [0; 3];
+    for k in 0..3 {
+        let distance = hit_j[k] - hit_i[k];
+        if distance % (tj - ti) != 0 {
+            return None;
+        }
+        velocity[k] = distance / (tj - ti);
+    }
+    let position = [hit_i[0] - velocity[0] * ti, hit_i[1] - velocity[1] * ti, hit_i[2] - velocity[2] * ti];
+
+    // verify that the rock hits every hailstone, and find at what time it does
+    let collision_times = hailstones.iter().map(|h| {
+        let offset = sub(position, h.position());
+        let closing = sub(velocity, h.velocity());
+        if closing == [0, 0, 0] {
+            return None;
+        }
+        solve_time(offset, closing).filter(|t| *t >= 0)
+    }).collect::<Option<Vec<i128>>>()?;
+    return Some(Rock {
+        position: (position[0], position[1], position[2]),
+        velocity: (velocity[0], velocity[1], velocity[2]),
+        collision_times,
+    });
 }
 
-#[aoc(day24, part2)]
-pub fn part2(input: &str) -> f64 {
-    let lines: Vec<Line> = input.lines().map(Line::new).collect();
-    for z in -499..500 {
-        let z = z as f64;
-        for y in -499..500 {
-            let y = y as f64;
-            for x in -499..500 {
-                let x = x as f64;
-                let line_a = lines[0].with_velocity(x, y, z);
-                let line_b = lines[1].with_velocity(x, y, z);
-                let line_c = lines[2].with_velocity(x, y, z);
-                if let Some(intersection_ab) = line_a.find_xyz_intersection(&line_b) {
-                    if let Some(intersection_ac) = line_a.find_xyz_intersection(&line_c) {
-                        if intersection_ab == intersection_ac {
-                            let (ix, iy, iz) = intersection_ab;
-                            return ix + iy + iz;
-                        }
-                    }
+#[derive(Debug, PartialEq)]
+pub struct Crossing {
+    pub a: usize,
+    pub b: usize,
+    pub point: (f64, f64),
+    pub times: (f64, f64),
+}
+
+// finds all pairs of hailstones whose future paths cross in the xy plane inside the test area, the times are the
+//  moments each of the two hailstones passes through the crossing point
+pub fn find_xy_crossings(lines: &[Line], min: f64, max: f64) -> Vec<Crossing> {
+    let mut crossings = vec![];
+    for a in 0..lines.len() {
+        for b in (a + 1)..lines.len() {
+            if let Some((point, times)) = lines[a].find_xy_intersection(&lines[b]) {
+                if point.0 >= min && point.0 <= max && point.1 >= min && point.1 <= max {
+                    crossings.push(Crossing { a, b, point, times });
                 }
             }
         }
     }
-    panic!("not found!");
+    return crossings;
+}
+
+pub fn count_xy_intersections(lines: &[Line], min: f64, max: f64) -> usize {
+    return find_xy_crossings(lines, min, max).len();
+}
+
+#[aoc(day24, part1)]
+pub fn part1(input: &str) -> usize {
+    let lines: Vec<Line> = input.lines().map(Line::new).collect();
+    return count_xy_intersections(&lines, 200000000000000f64, 400000000000000f64);
+}
+
+#[aoc(day24, part2)]
+pub fn part2(input: &str) -> i128 {
+    let lines: Vec<Line> = input.lines().map(Line::new).collect();
+    let rock = find_rock(&lines).expect("no rock hits every hailstone");
+    return rock.position.0 + rock.position.1 + rock.position.2;
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2};
+    use super::{Approach, count_xy_intersections, find_rock, find_xy_crossings, Crossing, Fraction, Intersection, Line, part1, part2, Rock};
 
     #[test]
     fn samples() {
@@ -135,7 +317,60 @@ mod tests {
 20, 25, 34 @ -2, -2, -4
 12, 31, 28 @ -1, -2, -1
 20, 19, 15 @  1, -5, -3";
-        assert_eq!(part1(example), 2);
-        assert_eq!(part2(example), 47f64);
+        let lines: Vec<Line> = example.lines().map(Line::new).collect();
+        assert_eq!(part1(example), 0);
+        assert_eq!(count_xy_intersections(&lines, 7f64, 27f64), 2);
+        assert_eq!(part2(example), 47);
+        assert_eq!(find_rock(&lines), Some(Rock {
+            position: (24, 13, 10),
+            velocity: (-3, 1, 2),
+            collision_times: vec![5, 3, 4, 6, 1],
+        }));
+    }
+
+    #[test]
+    fn crossings() {
+        let lines: Vec<Line> = ["0, 0, 0 @ 1, 1, 0", "10, 0, 0 @ -1, 1, 0", "0, 10, 0 @ 1, 0, 0", "0, 1, 0 @ 1, 1, 0"].iter()
+            .map(|text| Line::new(text))
+            .collect();
+        assert_eq!(find_xy_crossings(&lines, 0f64, 20f64), vec![
+            Crossing { a: 0, b: 1, point: (5f64, 5f64), times: (5f64, 5f64) },
+            Crossing { a: 0, b: 2, point: (10f64, 10f64), times: (10f64, 10f64) },
+            Crossing { a: 1, b: 2, point: (0f64, 10f64), times: (10f64, 0f64) },
+            Crossing { a: 1, b: 3, point: (4.5f64, 5.5f64), times: (5.5f64, 4.5f64) },
+            Crossing { a: 2, b: 3, point: (9f64, 10f64), times: (9f64, 9f64) },
+        ]);
+        assert_eq!(count_xy_intersections(&lines, 0f64, 6f64), 2);
+    }
+
+    #[test]
+    fn geometry() {
+        let a = Line::new("0, 0, 0 @ 1, 1, 1");
+        let fraction = |numerator: i128, denominator: i128| Fraction::new(numerator, denominator);
+
+        // paths that cross at different times
+        assert_eq!(a.find_xyz_intersection(&Line::new("3, 0, 0 @ 0, 2, 2")), Intersection::Point {
+            point: [fraction(3, 1), fraction(3, 1), fraction(3, 1)],
+            times: (fraction(3, 1), fraction(3, 2)),
+        });
+        // paths that crossed in the past, skew paths and parallel paths
+        assert_eq!(a.find_xyz_intersection(&Line::new("-3, 0, 0 @ 0, -2, -2")), Intersection::None);
+        assert_eq!(a.find_xyz_intersection(&Line::new("0, 1, 0 @ 1, 0, 0")), Intersection::None);
+        assert_eq!(a.find_xyz_intersection(&Line::new("1, 0, 0 @ 2, 2, 2")), Intersection::None);
+        // the same path towards each other, in the same direction, and away from each other
+        assert_eq!(a.find_xyz_intersection(&Line::new("5, 5, 5 @ -2, -2, -2")), Intersection::Collinear { start: [0, 0, 0], end: Some([5, 5, 5]) });
+        assert_eq!(a.find_xyz_intersection(&Line::new("5, 5, 5 @ 2, 2, 2")), Intersection::Collinear { start: [5, 5, 5], end: None });
+        assert_eq!(Line::new("5, 5, 5 @ 2, 2, 2").find_xyz_intersection(&a), Intersection::Collinear { start: [5, 5, 5], end: None });
+        assert_eq!(a.find_xyz_intersection(&Line::new("-5, -5, -5 @ -1, -1, -1")), Intersection::None);
+
+        // hailstones passing each other at a distance of 1
+        let b = Line::new("4, 0, 1 @ -1, 1, 1");
+        assert_eq!(a.closest_approach(&b), Approach { time: fraction(2, 1), distance_squared: fraction(1, 1) });
+        assert_eq!(a.min_distance(&b), 1f64);
+        // hailstones that collide, that are moving apart, and that move in parallel
+        assert_eq!(a.closest_approach(&Line::new("6, -3, 0 @ -1, 2, 1")).distance_squared, fraction(0, 1));
+        assert_eq!(a.closest_approach(&Line::new("1, 1, 1 @ 2, 1, 1")), Approach { time: fraction(0, 1), distance_squared: fraction(3, 1) });
+        assert_eq!(a.closest_approach(&Line::new("0, 3, 4 @ 1, 1, 1")).distance_squared, fraction(25, 1));
+        assert_eq!(a.closest_approach(&Line::new("1, 0, 0 @ 0, 2, 1")), Approach { time: fraction(1, 2), distance_squared: fraction(1, 2) });
     }
 }
@@ -1,47 +1,135 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+use crate::graphviz::{Graph, Style};
 
-#[aoc(day25, part1)]
-pub fn part1(input: &str) -> usize {
+#[derive(Debug)]
+pub struct Cut {
+    pub edges: Vec<(String, String)>,
+    pub sizes: (usize, usize),
+}
+
+pub fn parse_graph(input: &str) -> HashMap<String, Vec<String>> {
     let mut graph: HashMap<String, Vec<String>> = HashMap::new();
     input.lines().for_each(|line| {
         let splits = line.split(": ").collect::<Vec<&str>>();
         let from = splits[0];
-        let tos = splits[1].split(" ").collect::<Vec<&str>>();
+        let tos = splits[1].split(' ').collect::<Vec<&str>>();
         tos.iter().for_each(|to| {
             graph.entry(from.to_string()).or_default().push(to.to_string());
             graph.entry(to.to_string()).or_default().push(from.to_string());
         });
     });
-    let mut remove_connection = |from: String, to: String| {
-        let neighbors = graph.entry(from).or_default();
-        if let Some(index) = neighbors.iter().position(|v| *v == to) {
-            neighbors.swap_remove(index);
+    return graph;
+}
+
+// Finds the global minimum cut of an undirected graph by computing the maximum flow from a fixed source to every other
+//  node, as every cut separates that source from at least one other node.
+pub fn find_minimum_cut(graph: &HashMap<String, Vec<String>>) -> Cut {
+    // Give each node an index, sorted by name so that the result is deterministic
+    let mut names: Vec<&String> = graph.keys().collect();
+    names.sort();
+    let index_of: HashMap<&String, usize> = names.iter().enumerate().map(|(index, name)| (*name, index)).collect();
+    assert!(names.len() >= 2, "a cut requires at least two nodes");
+
+    // Each undirected edge becomes two arcs with capacity 1, arc i and arc i ^ 1 are each other's reverse
+    let mut arcs: Vec<usize> = vec![];
+    let mut outgoing: Vec<Vec<usize>> = vec![vec![]; names.len()];
+    names.iter().enumerate().for_each(|(from, name)| {
+        graph.get(*name).unwrap().iter().for_each(|neighbor| {
+            let to = *index_of.get(neighbor).unwrap();
+            if from < to {
+                outgoing[from].push(arcs.len());
+                arcs.push(to);
+                outgoing[to].push(arcs.len());
+                arcs.push(from);
+            }
+        });
+    });
+
+    // Find all nodes that can be reached from the source in the residual graph, returning the arc used to reach each
+    let reachable = |residual: &Vec<usize>, source: usize| -> Vec<Option<usize>> {
+        let mut via: Vec<Option<usize>> = vec![None; names.len()];
+        let mut visited = vec![false; names.len()];
+        visited[source] = true;
+        let mut open = VecDeque::from([source]);
+        while let Some(current) = open.pop_front() {
+            for arc in outgoing[current].iter() {
+                let next = arcs[*arc];
+                if residual[*arc] > 0 && !visited[next] {
+                    visited[next] = true;
+                    via[next] = Some(*arc);
+                    open.push_back(next);
+                }
+            }
         }
+        via[source] = Some(usize::MAX);
+        return via;
     };
 
-    // from visualization you can clearly see you need to remove:
-    // rcn -> xkf
-    remove_connection(String::from("rcn"), String::from("xkf"));
-    remove_connection(String::from("xkf"), String::from("rcn"));
-    // cms -> thk
-    remove_connection(String::from("cms"), String::from("thk"));
-    remove_connection(String::from("thk"), String::from("cms"));
-    // dht -> xmv
-    remove_connection(String::from("dht"), String::from("xmv"));
-    remove_connection(String::from("xmv"), String::from("dht"));
-
-    let mut visited = vec![];
-    let mut open = vec![];
-    open.push(graph.keys().nth(0).unwrap());
-    while let Some(current) = open.pop() {
-        visited.push(current);
-        graph.get(current).unwrap().iter().for_each(|neighbor| {
-            if !visited.contains(&neighbor) && !open.contains(&neighbor) {
-                open.push(neighbor);
+    let source = 0;
+    let mut best: Option<(usize, Vec<bool>)> = None;
+    for sink in 1..names.len() {
+        // Augment along shortest paths until the sink can no longer be reached
+        let mut residual = vec![1; arcs.len()];
+        let mut flow = 0;
+        let mut via = reachable(&residual, source);
+        while via[sink].is_some() {
+            // Stop early if this flow can no longer beat the best cut found so far
+            if best.as_ref().is_some_and(|(size, _)| flow >= *size) {
+                break;
+            }
+            let mut current = sink;
+            while current != source {
+                let arc = via[current].unwrap();
+                residual[arc] -= 1;
+                residual[arc ^ 1] += 1;
+                current = arcs[arc ^ 1];
             }
-        })
+            flow += 1;
+            via = reachable(&residual, source);
+        }
+        if via[sink].is_none() {
+            best = Some((flow, via.iter().map(|v| v.is_some()).collect()));
+        }
+    }
+
+    // Collect every edge that crosses from the source side to the other side
+    let (_, source_side) = best.unwrap();
+    let mut edges = vec![];
+    for (from, to) in arcs.iter().enumerate().step_by(2).map(|(arc, to)| (arcs[arc ^ 1], *to)) {
+        if source_side[from] != source_side[to] {
+            let (a, b) = if names[from] < names[to] { (from, to) } else { (to, from) };
+            edges.push((names[a].to_string(), names[b].to_string()));
+        }
     }
-    return visited.len() * (graph.len() - visited.len());
+    edges.sort();
+    let inside = source_side.iter().filter(|v| **v).count();
+    return Cut {
+        edges,
+        sizes: (inside, names.len() - inside),
+    };
+}
+
+// renders the wires as an undirected graphviz graph, labelling the wires of the given cut
+pub fn to_dot(graph: &HashMap<String, Vec<String>>, cut: Option<&Cut>) -> String {
+    let mut names: Vec<&String> = graph.keys().collect();
+    names.sort();
+    let mut dot = Graph::undirected();
+    names.iter().for_each(|name| { dot.node(name, None, Style::default()); });
+    names.iter().for_each(|from| {
+        let mut neighbors: Vec<&String> = graph.get(*from).unwrap().iter().filter(|to| *from < *to).collect();
+        neighbors.sort();
+        neighbors.iter().for_each(|to| {
+            let is_cut = cut.is_some_and(|cut| cut.edges.iter().any(|(a, b)| a == *from && b == *to));
+            dot.edge(from, to, if is_cut { Some(String::from("cut")) } else { None });
+        });
+    });
+    return dot.to_dot();
+}
+
+#[aoc(day25, part1)]
+pub fn part1(input: &str) -> usize {
+    let cut = find_minimum_cut(&parse_graph(input));
+    return cut.sizes.0 * cut.sizes.1;
 }
 
 #[aoc(day25, part2)]
@@ -51,7 +139,7 @@ pub fn part2(_input: &str) -> usize {
 
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2};
+    use super::{find_minimum_cut, parse_graph, part1, part2};
 
     #[test]
     fn samples() {
@@ -68,7 +156,16 @@ nvd: lhk
 lsr: lhk
 rzs: qnr cmg lsr rsh
 frs: qnr lhk lsr";
-        assert_eq!(part1(example), 20);
+        assert_eq!(part1(example), 54);
         assert_eq!(part2(example), 0);
+
+        let cut = find_minimum_cut(&parse_graph(example));
+        assert_eq!(cut.edges, vec![
+            (String::from("bvb"), String::from("cmg")),
+            (String::from("hfx"), String::from("pzl")),
+            (String::from("jqt"), String::from("nvd")),
+        ]);
+        assert_eq!(cut.sizes.0 * cut.sizes.1, 54);
+        assert_eq!(cut.sizes.0 + cut.sizes.1, 15);
     }
 }
@@ -1,68 +1,51 @@
 use std::cmp;
+use std::error::Error;
+use std::fmt;
+use crate::interval_set::IntervalSet;
 
 #[derive(Debug, Copy, Clone)]
 pub struct Range {
-    from: u32,
-    to: u32,
+    from: u64,
+    to: u64,
 }
 
-
 impl Range {
-    fn merge(mut ranges: Vec<Range>) -> Vec<Range> {
-        let initial_range_count = ranges.len();
-        ranges.sort_by_key(|range| range.from);
-        let mut range_index = 0;
-        let mut merged_ranges = vec![];
-        while range_index < initial_range_count {
-            let range = ranges[range_index];
-
-            // if this is the last range, add the remaining
-            if range_index == initial_range_count - 1 {
-                merged_ranges.push(Range { from: range.from, to: range.to })
-            // if the range ends before the next range starts, add it
-            } else {
-                let mut to = range.to;
-                while to >= ranges[range_index + 1].from {
-                    to = cmp::max(to, ranges[range_index + 1].to);
-                    range_index += 1;
-                    if range_index > initial_range_count - 2 {
-                        break;
-                    }
-                }
-                merged_ranges.push(Range { from: range.from, to });
-            }
-            range_index += 1;
-        }
-        return merged_ranges;
+    fn merge(ranges: Vec<Range>) -> Vec<Range> {
+        return ranges.iter()
+            .map(|range| range.from..range.to)
+            .collect::<IntervalSet<u64>>()
+            .iter()
+            .map(|range| Range { from: range.start, to: range.end })
+            .collect();
     }
 }
 
 #[derive(Debug)]
 pub struct Map {
-    destinations: Vec<u32>,
-    sources: Vec<u32>,
-    lengths: Vec<u32>,
+    destinations: Vec<u64>,
+    sources: Vec<u64>,
+    lengths: Vec<u64>,
 }
 
 impl Map {
-    fn convert(&self, value: u32) -> u32 {
+    fn convert(&self, value: u64) -> u64 {
         for i in 0..self.sources.len() {
             let source = self.sources[i];
-            if value < source || value >= source + self.lengths[i] {
+            if value < source || value - source >= self.lengths[i] {
                 continue;
             }
             return self.destinations[i] + (value - source);
         }
         return value;
     }
-    fn find_next_rule_from(&self, value: u32) -> Option<(
+    fn find_next_rule_from(&self, value: u64) -> Option<(
         /* index of the first rule found from value */ usize,
         /* whether value lies inside this rule */ bool
     )> {
         return self.sources.iter().enumerate()
-            .map(|(index, source)| (index, (source + self.lengths[index]) as i64 - (value as i64)))
-            .filter(|(_, v)| *v > 0)
-            .min_by_key(|(_, v)| *v)
+            .map(|(index, source)| (index, source + self.lengths[index]))
+            .filter(|(_, end)| *end > value)
+            .min_by_key(|(_, end)| *end)
             .map(|(index, _)| (index, value >= self.sources[index]));
     }
     fn convert_range(&self, range: Range) -> Vec<Range> {
@@ -123,12 +106,12 @@ impl Map {
 
 #[derive(Debug)]
 pub struct Almanac {
-    pub(crate) seeds: Vec<u32>,
+    pub(crate) seeds: Vec<u64>,
     maps: Vec<Map>,
 }
 
 impl Almanac {
-    fn convert(&self, value: u32) -> u32 {
+    fn convert(&self, value: u64) -> u64 {
         return self.maps.iter().fold(value, |acc, map| map.convert(acc));
     }
     pub(crate) fn convert_range(&self, range: Range) -> Vec<Range> {
@@ -139,22 +122,145 @@ impl Almanac {
             }
         );
     }
+    // composes all maps into a single map, by converting each segment of the map so far with the next map
+    pub fn compose(&self) -> PiecewiseMap {
+        let identity = Segment { from: 0, to: u64::MAX, destination: 0 };
+        let segments = self.maps.iter().fold(vec![identity], |segments, map| {
+            return segments.iter().flat_map(|segment| {
+                // convert_range returns the converted parts in the order of the range it converted
+                let mut from = segment.from;
+                map.convert_range(Range { from: segment.destination, to: segment.destination + (segment.to - segment.from) })
+                    .iter()
+                    .map(|range| {
+                        let to = from + (range.to - range.from);
+                        let part = Segment { from, to, destination: range.from };
+                        from = to;
+                        part
+                    })
+                    .collect::<Vec<Segment>>()
+            }).collect();
+        });
+        return PiecewiseMap::new(segments);
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Segment {
+    pub from: u64,
+    pub to: u64,
+    pub destination: u64,
+}
+
+// A map that moves each segment of values to its destination, the segments are sorted and cover all values below
+//  u64::MAX without overlapping, which makes a conversion a single binary search
+#[derive(Debug, PartialEq, Eq)]
+pub struct PiecewiseMap {
+    segments: Vec<Segment>,
+}
+
+impl PiecewiseMap {
+    fn new(mut segments: Vec<Segment>) -> PiecewiseMap {
+        segments.sort_by_key(|segment| segment.from);
+
+        // join segments that continue each other
+        let mut joined: Vec<Segment> = vec![];
+        for segment in segments {
+            match joined.last_mut() {
+                Some(last) if last.to == segment.from && last.destination + (last.to - last.from) == segment.destination => {
+                    last.to = segment.to;
+                }
+                _ => joined.push(segment),
+            }
+        }
+        return PiecewiseMap { segments: joined };
+    }
+
+    pub fn segments(&self) -> &[Segment] {
+        return &self.segments;
+    }
+
+    pub fn convert(&self, value: u64) -> u64 {
+        // a rule ends before u64::MAX, so the largest value is never moved and lies outside of every segment
+        if value == u64::MAX {
+            return value;
+        }
+        let index = self.segments.partition_point(|segment| segment.from <= value);
+        let segment = self.segments[index - 1];
+        return segment.destination + (value - segment.from);
+    }
+
+    // returns all values that are converted to a value inside the range
+    pub fn preimage(&self, range: std::ops::Range<u64>) -> IntervalSet<u64> {
+        return self.segments.iter().filter_map(|segment| {
+            let length = segment.to - segment.from;
+            let from = cmp::max(range.start, segment.destination);
+            let to = cmp::min(range.end, segment.destination + length);
+            if from >= to {
+                return None;
+            }
+            Some((segment.from + (from - segment.destination))..(segment.from + (to - segment.destination)))
+        }).collect();
+    }
+
+    // returns the map that converts back, which only exists if no two values are converted to the same value
+    pub fn inverse(&self) -> Option<PiecewiseMap> {
+        let mut segments: Vec<Segment> = self.segments.iter().map(|segment| Segment {
+            from: segment.destination,
+            to: segment.destination + (segment.to - segment.from),
+            destination: segment.from,
+        }).collect();
+        segments.sort_by_key(|segment| segment.from);
+        let mut expected_from = 0;
+        for segment in segments.iter() {
+            if segment.from != expected_from {
+                return None;
+            }
+            expected_from = segment.to;
+        }
+        return Some(PiecewiseMap::new(segments));
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ParseError {
+    line: usize,
+    message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
 }
 
+impl Error for ParseError {}
+
 #[aoc_generator(day5)]
-pub fn input_generator(input: &str) -> Almanac {
+pub fn input_generator(input: &str) -> Result<Almanac, ParseError> {
+    let error = |line: usize, message: String| ParseError { line: line + 1, message };
+    let parse = |line: usize, number: &str| number.parse::<u64>()
+        .map_err(|e| error(line, format!("'{}' is not a valid number ({})", number, e)));
     let lines: Vec<&str> = input.lines().collect();
-    let seeds = lines[0].strip_prefix("seeds:").unwrap().split_whitespace().map(|seed| seed.parse().unwrap()).collect();
+    let seeds = lines.first()
+        .and_then(|line| line.strip_prefix("seeds:"))
+        .ok_or_else(|| error(0, String::from("expected the seeds")))?
+        .split_whitespace()
+        .map(|seed| parse(0, seed))
+        .collect::<Result<Vec<u64>, ParseError>>()?;
+    if seeds.is_empty() {
+        return Err(error(0, String::from("expected at least one seed")));
+    }
     let mut maps = vec![];
     let mut destinations = vec![];
     let mut sources = vec![];
     let mut lengths = vec![];
-    for i in 1..lines.len() {
-        let line = lines[i];
+    let mut has_header = false;
+    for (i, line) in lines.iter().enumerate().skip(1) {
         if line.chars().count() == 0 {
             continue;
         }
         if line.contains("map") {
+            has_header = true;
             if !sources.is_empty() {
                 maps.push(Map { destinations: destinations.clone(), sources: sources.clone(), lengths: lengths.clone() });
                 destinations.clear();
@@ -163,50 +269,96 @@ pub fn input_generator(input: &str) -> Almanac {
             }
             continue;
         }
-        let mut numbers = line.split(' ').map(|number| number.parse().unwrap());
-        destinations.push(numbers.next().unwrap());
-        sources.push(numbers.next().unwrap());
-        lengths.push(numbers.next().unwrap());
+        if !has_header {
+            return Err(error(i, String::from("expected a map header before the first rule")));
+        }
+        let numbers = line.split_whitespace().map(|number| parse(i, number)).collect::<Result<Vec<u64>, ParseError>>()?;
+        let (destination, source, length) = match numbers[..] {
+            [destination, source, length] => (destination, source, length),
+            _ => return Err(error(i, format!("expected 3 numbers, found {}", numbers.len()))),
+        };
+        if source.checked_add(length).is_none() || destination.checked_add(length).is_none() {
+            return Err(error(i, String::from("rule extends beyond the largest possible value")));
+        }
+        if length == 0 {
+            continue;
+        }
+        destinations.push(destination);
+        sources.push(source);
+        lengths.push(length);
     };
     if !sources.is_empty() {
         maps.push(Map { destinations: destinations.clone(), sources: sources.clone(), lengths: lengths.clone() });
     }
-    return Almanac {
+    return Ok(Almanac {
         seeds,
         maps,
+    })
+}
+
+// returns the seed ranges, as pairs of a start and a length, which are reported against the seeds line
+fn seed_ranges(almanac: &Almanac) -> Result<Vec<Range>, ParseError> {
+    let error = |message: &str| ParseError { line: 1, message: message.to_string() };
+    if !almanac.seeds.len().is_multiple_of(2) {
+        return Err(error("expected pairs of a seed and a length, found an odd number of values"));
     }
+    return almanac.seeds.chunks_exact(2).map(|range| {
+        if range[1] == 0 {
+            return Err(error("seed range is empty"));
+        }
+        Ok(Range {
+            from: range[0],
+            to: range[0].checked_add(range[1]).ok_or_else(|| error("seed range extends beyond the largest possible value"))?,
+        })
+    }).collect();
 }
 
 #[aoc(day5, part1)]
-pub fn part1(almanac: &Almanac) -> u32 {
+pub fn part1(almanac: &Almanac) -> u64 {
     return almanac.seeds.iter().map(|seed| almanac.convert(*seed)).min().unwrap();
 }
 
 #[aoc(day5, part2, ranges)]
-pub fn part2_sets(almanac: &Almanac) -> u32 {
-    let start_ranges: Vec<Range> = almanac.seeds.chunks(2).map(|range| Range { from: range[0], to: range[0] + range[1] }).collect();
+pub fn part2_sets(almanac: &Almanac) -> Result<u64, ParseError> {
+    let start_ranges: Vec<Range> = seed_ranges(almanac)?;
     let end_ranges: Vec<Range> = start_ranges.iter().flat_map(|range| almanac.convert_range(*range)).collect();
-    return end_ranges.iter().map(|range| range.from).min().unwrap();
+    return Ok(end_ranges.iter().map(|range| range.from).min().unwrap());
+}
+
+#[aoc(day5, part2, composed)]
+pub fn part2_composed(almanac: &Almanac) -> Result<u64, ParseError> {
+    let seeds: IntervalSet<u64> = seed_ranges(almanac)?.iter().map(|range| range.from..range.to).collect();
+
+    // walk the segments in order of location, until no later segment can reach a lower location
+    let mut segments = almanac.compose().segments().to_vec();
+    segments.sort_by_key(|segment| segment.destination);
+    let mut min = u64::MAX;
+    for segment in segments {
+        if segment.destination >= min {
+            break;
+        }
+        let reached = seeds.intersection(&IntervalSet::from_range(segment.from..segment.to));
+        if let Some(first) = reached.iter().next() {
+            min = cmp::min(min, segment.destination + (first.start - segment.from));
+        }
+    }
+    return Ok(min);
 }
 
 #[aoc(day5, part2, naive)]
-pub fn part2_naive(almanac: &Almanac) -> u32 {
-    let mut seed_index = 0;
-    let mut min = u32::MAX;
-    while seed_index < almanac.seeds.len() {
-        let from = almanac.seeds[seed_index];
-        let to = from + almanac.seeds[seed_index + 1];
-        for seed_value in from..to {
+pub fn part2_naive(almanac: &Almanac) -> Result<u64, ParseError> {
+    let mut min = u64::MAX;
+    for range in seed_ranges(almanac)? {
+        for seed_value in range.from..range.to {
             min = cmp::min(almanac.convert(seed_value), min);
         }
-        seed_index += 2;
     }
-    return min;
+    return Ok(min);
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{Almanac, input_generator, part1, part2_sets, part2_naive, Range};
+    use super::{Almanac, input_generator, ParseError, part1, part2_composed, part2_sets, part2_naive, Range};
 
     fn get_example_almanac() -> Almanac {
         let example = "seeds: 79 14 55 13
@@ -242,7 +394,7 @@ temperature-to-humidity map:
 humidity-to-location map:
 60 56 37
 56 93 4";
-        return input_generator(example);
+        return input_generator(example).unwrap();
     }
 
     // part 1
@@ -254,11 +406,77 @@ humidity-to-location map:
     // part 2
     #[test]
     fn sample2_sets() {
-        assert_eq!(part2_sets(&get_example_almanac()), 46);
+        assert_eq!(part2_sets(&get_example_almanac()), Ok(46));
+    }
+    #[test]
+    fn sample2_composed() {
+        assert_eq!(part2_composed(&get_example_almanac()), Ok(46));
     }
     #[test]
     fn sample2_naive() {
-        assert_eq!(part2_naive(&get_example_almanac()), 46);
+        assert_eq!(part2_naive(&get_example_almanac()), Ok(46));
+    }
+
+    #[test]
+    fn large_values() {
+        let almanac = input_generator("seeds: 18446744073709551610 5 3 2
+
+seed-to-location map:
+0 18446744073709551605 10
+18446744073709551600 0 4").unwrap();
+        assert_eq!(part1(&almanac), 5);
+        assert_eq!(part2_sets(&almanac), Ok(4));
+        assert_eq!(part2_naive(&almanac), Ok(4));
+        assert_eq!(part2_composed(&almanac), Ok(4));
+    }
+
+    #[test]
+    fn composed() {
+        let almanac = get_example_almanac();
+        let composed = almanac.compose();
+        let inverse = composed.inverse().unwrap();
+        for seed in 0..200 {
+            assert_eq!(composed.convert(seed), almanac.convert(seed));
+            assert_eq!(inverse.convert(composed.convert(seed)), seed);
+        }
+        assert_eq!(composed.convert(u64::MAX - 1), u64::MAX - 1);
+        assert_eq!(composed.convert(u64::MAX), u64::MAX);
+
+        // a rule that ends right below the largest value still leaves it in place
+        let almanac = input_generator("seeds: 1\n\na map:\n0 18446744073709551605 10").unwrap();
+        assert_eq!(almanac.compose().convert(u64::MAX - 1), 9);
+        assert_eq!(almanac.compose().convert(u64::MAX), almanac.convert(u64::MAX));
+        assert_eq!(almanac.compose().convert(u64::MAX), u64::MAX);
+
+        // seeds 82 and 83 land in locations 46 and 47
+        assert_eq!(composed.preimage(46..48).iter().cloned().collect::<Vec<_>>(), vec![82..84]);
+
+        // two rules that convert to the same values cannot be inverted
+        let almanac = input_generator("seeds: 1\n\na map:\n0 10 5").unwrap();
+        assert_eq!(almanac.compose().inverse(), None);
+        assert_eq!(almanac.compose().preimage(0..2).iter().cloned().collect::<Vec<_>>(), vec![0..2, 10..12]);
+    }
+
+    #[test]
+    fn malformed() {
+        let error = |line: usize, message: &str| Err(ParseError { line, message: message.to_string() });
+        assert_eq!(input_generator("seeds: 1 x").map(|_| ()), error(1, "'x' is not a valid number (invalid digit found in string)"));
+        assert_eq!(input_generator("seeds: 1\n\n1 2 3").map(|_| ()), error(3, "expected a map header before the first rule"));
+        assert_eq!(input_generator("seeds: 1\n\na map:\n1 2").map(|_| ()), error(4, "expected 3 numbers, found 2"));
+        assert_eq!(input_generator("seeds: 1\n\na map:\n1 18446744073709551615 2").map(|_| ()), error(4, "rule extends beyond the largest possible value"));
+
+        // the seed ranges are only checked when they are used, since part 1 reads the seeds as single values
+        let almanac = input_generator("seeds: 18446744073709551615 5\n\na map:\n1 2 3").unwrap();
+        assert_eq!(part2_sets(&almanac).map(|_| ()), error(1, "seed range extends beyond the largest possible value"));
+        assert_eq!(part2_composed(&almanac).map(|_| ()), error(1, "seed range extends beyond the largest possible value"));
+        let almanac = input_generator("seeds: 1 2 3\n\na map:\n1 2 3").unwrap();
+        assert_eq!(part1(&almanac), 1);
+        assert_eq!(part2_naive(&almanac).map(|_| ()), error(1, "expected pairs of a seed and a length, found an odd number of values"));
+        let almanac = input_generator("seeds: 5 0\n\na map:\n1 2 3").unwrap();
+        assert_eq!(part2_sets(&almanac).map(|_| ()), error(1, "seed range is empty"));
+        assert_eq!(part2_composed(&almanac).map(|_| ()), error(1, "seed range is empty"));
+        assert_eq!(part2_naive(&almanac).map(|_| ()), error(1, "seed range is empty"));
+        assert_eq!(input_generator("seeds:\n\na map:\n1 2 3").map(|_| ()), error(1, "expected at least one seed"));
     }
 
     // some tests for the helpers
@@ -1,59 +1,105 @@
+use std::cmp;
+
 pub struct Race {
     time: i64,
     distance: i64,
 }
 
 impl Race {
-    fn number_of_ways_to_win(&self) -> i64 {
-        let mut result = 0;
-        for i in 0..self.time {
-            if i * (self.time - i) > self.distance {
-                result += 1;
-            }
+    // returns the first and last hold time that beat the record, the distance h * (time - h) is larger than the record
+    //  between the roots of h^2 - time * h + distance, which lie symmetrical around time / 2
+    pub fn winning_hold_times(&self) -> Option<(i64, i64)> {
+        let time = self.time as i128;
+        let distance = self.distance as i128;
+        let discriminant = time * time - 4 * distance;
+        if discriminant < 0 {
+            return None;
+        }
+        let wins = |hold: i128| hold * (time - hold) > distance;
+
+        // the integer square root puts the first hold time within one of the exact root
+        let mut first = cmp::max(0, (time - isqrt(discriminant)) / 2);
+        while first > 0 && wins(first - 1) {
+            first -= 1;
         }
-        return result;
+        while first <= time / 2 && !wins(first) {
+            first += 1;
+        }
+        let last = time - first;
+        if first > last {
+            return None;
+        }
+        return Some((first as i64, last as i64));
+    }
+
+    pub fn number_of_ways_to_win(&self) -> i64 {
+        return self.winning_hold_times().map_or(0, |(first, last)| last - first + 1);
     }
 }
 
-#[aoc_generator(day6)]
-pub fn input_generator(input: &str) -> Vec<Race> {
+// returns the largest integer whose square is at most n
+fn isqrt(n: i128) -> i128 {
+    if n < 2 {
+        return n;
+    }
+    let mut x = n;
+    let mut y = (x + 1) / 2;
+    while y < x {
+        x = y;
+        y = (x + n / x) / 2;
+    }
+    return x;
+}
+
+// parses the races, when kerned the spaces between the numbers are ignored and each line forms a single number
+fn parse(input: &str, kerned: bool) -> Vec<Race> {
+    let numbers = |line: Option<&str>, prefix: &str| -> Vec<i64> {
+        let values = line.unwrap().strip_prefix(prefix).unwrap();
+        if kerned {
+            return vec![values.split_whitespace().collect::<String>().parse().unwrap()];
+        }
+        return values.split_whitespace().map(|value| value.parse().unwrap()).collect();
+    };
     let mut lines = input.lines();
-    let times: Vec<i64> = lines.next().unwrap()
-        .strip_prefix("Time:").unwrap()
-        .split_whitespace()
-        .map(|time| time.parse().unwrap())
-        .collect();
-    let distances: Vec<i64> = lines.next().unwrap()
-        .strip_prefix("Distance:").unwrap()
-        .split_whitespace()
-        .map(|distance| distance.parse().unwrap())
-        .collect();
+    let times: Vec<i64> = numbers(lines.next(), "Time:");
+    let distances: Vec<i64> = numbers(lines.next(), "Distance:");
     return times.iter().enumerate().map(|(index, _)| Race {
         time: times[index],
         distance: distances[index],
     }).collect();
 }
 
+#[aoc_generator(day6, part1)]
+pub fn input_generator(input: &str) -> Vec<Race> {
+    return parse(input, false);
+}
+
+#[aoc_generator(day6, part2)]
+pub fn input_generator_kerned(input: &str) -> Vec<Race> {
+    return parse(input, true);
+}
+
 #[aoc(day6, part1)]
 pub fn part1(races: &[Race]) -> i64 {
     return races.iter().fold(1, |acc, race| acc * race.number_of_ways_to_win());
 }
 
 #[aoc(day6, part2)]
-pub fn part2(_races: &[Race]) -> i64 {
-    return Race {
-        time: 56717999,
-        distance: 334113513502430,
-    }.number_of_ways_to_win();
+pub fn part2(races: &[Race]) -> i64 {
+    return match races {
+        [race] => race.number_of_ways_to_win(),
+        _ => panic!("expected a single kerned race, found {}", races.len()),
+    };
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{Race, input_generator, part1, part2};
+    use super::{isqrt, Race, input_generator, input_generator_kerned, part1, part2};
+
+    const EXAMPLE: &str = "Time:      7  15   30\nDistance:  9  40  200";
 
     fn get_example() -> Vec<Race> {
-        let example = "Time:      7  15   30\nDistance:  9  40  200";
-        return input_generator(example);
+        return input_generator(EXAMPLE);
     }
 
     // part 1
@@ -65,7 +111,25 @@ mod tests {
     // part 2
     #[test]
     fn sample2() {
-        assert_eq!(part2(get_example().as_ref()), 43364472);
+        assert_eq!(part2(input_generator_kerned(EXAMPLE).as_ref()), 71503);
+    }
+
+    #[test]
+    fn winning_hold_times() {
+        assert_eq!(Race { time: 7, distance: 9 }.winning_hold_times(), Some((2, 5)));
+        assert_eq!(Race { time: 30, distance: 200 }.winning_hold_times(), Some((11, 19)));
+        assert_eq!(Race { time: 71530, distance: 940200 }.winning_hold_times(), Some((14, 71516)));
+        assert_eq!(Race { time: 4, distance: 4 }.winning_hold_times(), None);
+        assert_eq!(Race { time: 4, distance: 3 }.winning_hold_times(), Some((2, 2)));
+        assert_eq!(Race { time: 3, distance: 100 }.winning_hold_times(), None);
+        for time in 0..60 {
+            for distance in 0..200 {
+                let race = Race { time, distance };
+                let expected = (0..=time).filter(|hold| hold * (time - hold) > distance).count() as i64;
+                assert_eq!(race.number_of_ways_to_win(), expected);
+            }
+        }
+        assert_eq!(isqrt(i64::MAX as i128 * i64::MAX as i128), i64::MAX as i128);
     }
 
 }
@@ -1,10 +1,18 @@
 use std::cmp::Ordering;
+use std::fmt;
 
 #[derive(Debug, Eq)]
 pub struct Hand {
     cards: Vec<usize>,
     hand_type: usize,
     bid: usize,
+    explanation: Explanation,
+}
+
+impl Hand {
+    pub fn explanation(&self) -> &Explanation {
+        return &self.explanation;
+    }
 }
 
 impl Ord for Hand {
@@ -29,70 +37,139 @@ impl PartialEq for Hand {
     }
 }
 
-pub fn get_cards(input: &str, _joker: Option<char>) -> Vec<Hand> {
-    let cards = "23456789TJQKA";
-    let joker = _joker.map(|char| cards.find(char).unwrap() + 1);
-    println!("Joker at {:?}", joker);
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Substitution {
+    pub position: usize,
+    pub wildcard: char,
+    pub card: char,
+}
+
+// Explains how a hand is ranked, the hand type is the index in the hand types of the ruleset and the hand is compared
+//  to hands of the same type by the tie-break key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Explanation {
+    pub cards: String,
+    pub substitutions: Vec<Substitution>,
+    pub hand_type: usize,
+    pub groups: Vec<usize>,
+    pub tie_break: Vec<usize>,
+}
+
+impl Explanation {
+    // returns the cards with each wildcard replaced by its substitution
+    pub fn substituted(&self) -> String {
+        let mut cards: Vec<char> = self.cards.chars().collect();
+        self.substitutions.iter().for_each(|substitution| cards[substitution.position] = substitution.card);
+        return cards.iter().collect();
+    }
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} as {}: hand type {} {:?}, tie-break {:?}", self.cards, self.substituted(), self.hand_type, self.groups, self.tie_break)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Ruleset {
+    order: Vec<char>,
+    hand_size: usize,
+    wildcards: Vec<char>,
+    hand_types: Vec<Vec<usize>>,
+}
+
+impl Ruleset {
+    // creates a ruleset, the card order and the hand types are given from weakest to strongest, where each hand type is
+    //  the size of each group of equal cards
+    pub fn new(order: &str, hand_size: usize, wildcards: &str, hand_types: Vec<Vec<usize>>) -> Ruleset {
+        let order: Vec<char> = order.chars().collect();
+        let wildcards: Vec<char> = wildcards.chars().collect();
+        wildcards.iter().for_each(|card| assert!(order.contains(card), "wildcard {} is not a card", card));
+        let hand_types = hand_types.into_iter().map(|mut groups| {
+            groups.sort_by(|a, b| b.cmp(a));
+            assert_eq!(groups.iter().sum::<usize>(), hand_size, "hand type {:?} does not fit the hand size", groups);
+            groups
+        }).collect();
+        Ruleset { order, hand_size, wildcards, hand_types }
+    }
+
+    pub fn standard() -> Ruleset {
+        Ruleset::new("23456789TJQKA", 5, "", vec![
+            vec![1, 1, 1, 1, 1],
+            vec![2, 1, 1, 1],
+            vec![2, 2, 1],
+            vec![3, 1, 1],
+            vec![3, 2],
+            vec![4, 1],
+            vec![5],
+        ])
+    }
+
+    pub fn with_wildcards(self, wildcards: &str) -> Ruleset {
+        Ruleset::new(&self.order.iter().collect::<String>(), self.hand_size, wildcards, self.hand_types)
+    }
+
+    // returns the value of a card for breaking ties, where wildcards are weaker than all other cards
+    fn value(&self, card: char) -> usize {
+        let wild = self.order.iter().filter(|c| self.wildcards.contains(c));
+        let other = self.order.iter().filter(|c| !self.wildcards.contains(c));
+        return wild.chain(other).position(|c| *c == card)
+            .unwrap_or_else(|| panic!("{} is not a card", card));
+    }
+
+    // finds the strongest hand type, each group of equal cards must fit in its own group of the hand type, as the
+    //  wildcards can fill up the rest
+    pub fn explain(&self, cards: &str) -> Explanation {
+        // groups of equal cards that are not wild, the largest and then strongest first
+        let mut groups: Vec<(char, usize)> = self.order.iter()
+            .filter(|card| !self.wildcards.contains(card))
+            .map(|card| (*card, cards.chars().filter(|c| c == card).count()))
+            .filter(|(_, count)| *count > 0)
+            .collect();
+        groups.sort_by(|a, b| b.1.cmp(&a.1).then(self.value(b.0).cmp(&self.value(a.0))));
+        let hand_type = (0..self.hand_types.len()).rev()
+            .find(|index| {
+                let hand_type = &self.hand_types[*index];
+                groups.len() <= hand_type.len() && groups.iter().zip(hand_type.iter()).all(|((_, count), size)| count <= size)
+            })
+            .unwrap_or_else(|| panic!("no hand type matches {}", cards));
+
+        // fill up each group with wildcards, where groups that consist of only wildcards become the strongest card
+        //  that isn't in the hand yet, or '?' if every card is
+        let mut unused = self.order.iter().rev().filter(|card| !self.wildcards.contains(card) && !cards.contains(**card));
+        let mut fills = vec![];
+        self.hand_types[hand_type].iter().enumerate().for_each(|(index, size)| {
+            let (card, count) = groups.get(index).copied().unwrap_or_else(|| (*unused.next().unwrap_or(&'?'), 0));
+            (count..*size).for_each(|_| fills.push(card));
+        });
+        let substitutions = cards.chars().enumerate()
+            .filter(|(_, card)| self.wildcards.contains(card))
+            .zip(fills)
+            .map(|((position, wildcard), card)| Substitution { position, wildcard, card })
+            .collect();
+        return Explanation {
+            cards: cards.to_string(),
+            substitutions,
+            hand_type,
+            groups: self.hand_types[hand_type].clone(),
+            tie_break: cards.chars().map(|card| self.value(card)).collect(),
+        };
+    }
+}
+
+pub fn get_cards(input: &str, ruleset: &Ruleset) -> Vec<Hand> {
     let mut hands: Vec<Hand> = input.lines().map(|line| {
         // Bind cards and bid
-        let cards: Vec<usize> = line[0..5].chars().map(|char| cards.find(char).unwrap() + 1).collect();
-        let bid = line[6..].parse().unwrap();
-
-        // Find hand type
-        let mut ordered = cards.clone();
-        ordered.sort();
-        let mut last_index = 0;
-        let mut last_seen = ordered[0];
-        let mut has_jokers = !joker.is_none() && last_seen == joker.unwrap();
-        let mut same_joker_index = 0;
-        let mut same = vec![0];
-        for i in 0..ordered.len() {
-            if last_seen == ordered[i] {
-                same[last_index] = same[last_index] + 1;
-            } else {
-                same.push(1);
-                last_index += 1;
-                last_seen = ordered[i];
-                if !joker.is_none() && last_seen == joker.unwrap() {
-                    has_jokers = true;
-                    same_joker_index = last_index;
-                }
-            }
-        }
-        let mut number_of_jokers = 0;
-        if has_jokers && same.len() > 1 {
-            number_of_jokers = same[same_joker_index];
-            println!("{} {:?}[{}] {}", line, same, same_joker_index, number_of_jokers);
-            same = same.iter().enumerate().filter_map(|(index, value)| {
-                if index == same_joker_index {
-                    return None;
-                }
-                return Some(*value);
-            }).collect();
-        }
-        same.sort();
-        if number_of_jokers > 0 {
-            same[last_index - 1] += number_of_jokers;
-        }
-        let hand_type = match same[..] {
-            [5] => 6,
-            [1, 4] => 5,
-            [2, 3] => 4,
-            [1, 1, 3] => 3,
-            [1, 2, 2] => 2,
-            [1, 1, 1, 2] => 1,
-            [1, 1, 1, 1, 1] => 0,
-            _ => { println!("{}", line); panic!("hand_type not found") },
-        };
+        let (cards, bid) = line.split_once(' ').unwrap();
+        assert_eq!(cards.chars().count(), ruleset.hand_size, "{} does not have {} cards", cards, ruleset.hand_size);
 
         // Create hand with bid
+        let explanation = ruleset.explain(cards);
         return Hand {
-            cards: cards.iter().map(|card| {
-                if !joker.is_none() && *card == joker.unwrap() { return 0; }
-                return *card;
-            }).collect(),
-            hand_type,
-            bid,
+            cards: explanation.tie_break.clone(),
+            hand_type: explanation.hand_type,
+            bid: bid.trim().parse().unwrap(),
+            explanation,
         }
     }).collect();
     hands.sort();
@@ -101,17 +178,17 @@ pub fn get_cards(input: &str, _joker: Option<char>) -> Vec<Hand> {
 
 #[aoc(day7, part1)]
 pub fn part1(input: &str) -> usize {
-    return get_cards(input, None).iter().enumerate().fold(0, |acc, (rank, hand)| acc + (rank + 1) * hand.bid);
+    return get_cards(input, &Ruleset::standard()).iter().enumerate().fold(0, |acc, (rank, hand)| acc + (rank + 1) * hand.bid);
 }
 
 #[aoc(day7, part2)]
 pub fn part2(input: &str) -> usize {
-    return get_cards(input, Some('J')).iter().enumerate().fold(0, |acc, (rank, hand)| acc + (rank + 1) * hand.bid);
+    return get_cards(input, &Ruleset::standard().with_wildcards("J")).iter().enumerate().fold(0, |acc, (rank, hand)| acc + (rank + 1) * hand.bid);
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2};
+    use super::{Explanation, get_cards, part1, part2, Ruleset, Substitution};
 
     // part 1
     #[test]
@@ -126,4 +203,56 @@ mod tests {
         assert_eq!(part2("32T3K 765\nT55J5 684\nKK677 28\nKTJJT 220\nQQQJA 483"), 5905);
     }
 
+    #[test]
+    fn rulesets() {
+        let winnings = |input: &str, ruleset: &Ruleset| get_cards(input, ruleset).iter().enumerate()
+            .fold(0, |acc, (rank, hand)| acc + (rank + 1) * hand.bid);
+
+        // with both jacks and twos wild, 2J345 becomes three of a kind and beats the pair of kings
+        assert_eq!(winnings("2J345 1\nKK734 10", &Ruleset::standard()), 1 + 2 * 10);
+        assert_eq!(winnings("2J345 1\nKK734 10", &Ruleset::standard().with_wildcards("J")), 1 + 2 * 10);
+        assert_eq!(winnings("2J345 1\nKK734 10", &Ruleset::standard().with_wildcards("J2")), 10 + 2 * 1);
+        // but a tie is broken by the first card, where wildcards are weaker than any other card
+        assert_eq!(winnings("2J345 1\nKK234 10\n22223 100", &Ruleset::standard().with_wildcards("J2")), 1 + 2 * 10 + 3 * 100);
+
+        // six card hands with a six of a kind
+        let six = Ruleset::new("123456", 6, "1", vec![
+            vec![1, 1, 1, 1, 1, 1],
+            vec![2, 1, 1, 1, 1],
+            vec![2, 2, 1, 1],
+            vec![2, 2, 2],
+            vec![3, 1, 1, 1],
+            vec![3, 2, 1],
+            vec![3, 3],
+            vec![4, 1, 1],
+            vec![4, 2],
+            vec![5, 1],
+            vec![6],
+        ]);
+        assert_eq!(winnings("666665 1\n111111 10\n123456 100\n223344 1000", &six), 100 + 2 * 1000 + 3 + 4 * 10);
+    }
+
+    #[test]
+    fn explanations() {
+        let ruleset = Ruleset::standard().with_wildcards("J");
+        let hands = get_cards("32T3K 765\nT55J5 684\nKK677 28\nKTJJT 220\nQQQJA 483", &ruleset);
+        let explanation = hands.iter().find(|hand| hand.bid == 220).unwrap().explanation();
+        assert_eq!(*explanation, Explanation {
+            cards: String::from("KTJJT"),
+            substitutions: vec![
+                Substitution { position: 2, wildcard: 'J', card: 'T' },
+                Substitution { position: 3, wildcard: 'J', card: 'T' },
+            ],
+            hand_type: 5,
+            groups: vec![4, 1],
+            tie_break: vec![11, 9, 0, 0, 9],
+        });
+        assert_eq!(explanation.to_string(), "KTJJT as KTTTT: hand type 5 [4, 1], tie-break [11, 9, 0, 0, 9]");
+
+        // wildcards join the strongest of the largest groups, or become the strongest missing card
+        assert_eq!(ruleset.explain("KQJQK").substituted(), "KQKQK");
+        assert_eq!(ruleset.explain("JJJJJ").substituted(), "AAAAA");
+        assert_eq!(Ruleset::standard().with_wildcards("J2").explain("2J345").substituted(), "55345");
+        assert!(Ruleset::standard().explain("2J345").substitutions.is_empty());
+    }
 }
@@ -1,49 +1,107 @@
 use std::collections::HashMap;
+use crate::graphviz::{Graph, Style};
 
+// The network compiled into a dense graph, where each node is referred to by its index
 #[derive(Debug)]
-pub struct Node {
-    left: String,
-    right: String,
+pub struct Map {
+    instructions: Vec<u8>,
+    names: Vec<String>,
+    left: Vec<usize>,
+    right: Vec<usize>,
+    indices: HashMap<String, usize>,
 }
 
-#[derive(Debug)]
-pub struct Map {
-    instructions: String,
-    nodes: HashMap<String, Node>,
+impl Map {
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        return self.indices.get(name).copied();
+    }
+
+    pub fn name(&self, node: usize) -> &str {
+        return self.names[node].as_str();
+    }
+
+    // returns the node reached from the node by the instruction of the given step
+    pub fn next(&self, node: usize, step: usize) -> usize {
+        if self.instructions[step % self.instructions.len()] == b'L' {
+            return self.left[node];
+        }
+        return self.right[node];
+    }
+
+    // walks the network from the start, yielding each (step, node) on the way for which the predicate holds, starting
+    //  with the start itself at step 0. The walk never ends on its own.
+    pub fn walk<P: FnMut(usize) -> bool>(&self, start: usize, predicate: P) -> Walk<'_, P> {
+        Walk { map: self, node: start, step: 0, predicate }
+    }
+
+    // renders the network as a graphviz graph, with start nodes in green and end nodes in red
+    pub fn to_dot(&self) -> String {
+        let mut nodes: Vec<usize> = (0..self.names.len()).collect();
+        nodes.sort_by_key(|node| self.name(*node));
+        let mut graph = Graph::directed();
+        nodes.iter().for_each(|node| {
+            let name = self.name(*node);
+            let style = if name.ends_with('A') {
+                Style::color("green")
+            } else if name.ends_with('Z') {
+                Style::color("red")
+            } else {
+                Style::default()
+            };
+            graph.node(name, None, style);
+        });
+        nodes.iter().for_each(|node| {
+            graph.edge(self.name(*node), self.name(self.left[*node]), Some(String::from("L")));
+            graph.edge(self.name(*node), self.name(self.right[*node]), Some(String::from("R")));
+        });
+        return graph.to_dot();
+    }
+}
+
+pub struct Walk<'a, P> {
+    map: &'a Map,
+    node: usize,
+    step: usize,
+    predicate: P,
+}
+
+impl<P: FnMut(usize) -> bool> Iterator for Walk<'_, P> {
+    type Item = (usize, usize);
+
+    fn next(&mut self) -> Option<(usize, usize)> {
+        loop {
+            let (step, node) = (self.step, self.node);
+            self.node = self.map.next(node, step);
+            self.step += 1;
+            if (self.predicate)(node) {
+                return Some((step, node));
+            }
+        }
+    }
 }
 
 #[aoc_generator(day8)]
 pub fn input_generator(input: &str) -> Map {
-    let mut nodes = HashMap::new();
-    for line in input.lines().skip(2) {
-        let name: String = line.chars().take(3).collect();
-        let left = line.chars().skip(7).take(3).collect();
-        let right = line.chars().skip(12).take(3).collect();
-        nodes.insert(name, Node {
-            left,
-            right,
-        });
-    }
+    let lines: Vec<(&str, &str, &str)> = input.lines().skip(2).map(|line| {
+        let (name, children) = line.split_once(" = ").unwrap();
+        let (left, right) = children.trim_matches(|c| c == '(' || c == ')').split_once(", ").unwrap();
+        (name, left, right)
+    }).collect();
+    let indices: HashMap<String, usize> = lines.iter().enumerate().map(|(index, (name, _, _))| (name.to_string(), index)).collect();
+    let index_of = |name: &str| *indices.get(name).unwrap_or_else(|| panic!("node {} does not exist", name));
     return Map {
-        instructions: input.lines().next().unwrap().to_string(),
-        nodes,
+        instructions: input.lines().next().unwrap().bytes().collect(),
+        names: lines.iter().map(|(name, _, _)| name.to_string()).collect(),
+        left: lines.iter().map(|(_, left, _)| index_of(left)).collect(),
+        right: lines.iter().map(|(_, _, right)| index_of(right)).collect(),
+        indices,
     }
 }
 
 #[aoc(day8, part1)]
 pub fn part1(map: &Map) -> i32 {
-    let mut steps = 0;
-    let mut current = "AAA";
-    while current != "ZZZ" {
-        let direction = map.instructions.chars().nth(steps % map.instructions.chars().count()).unwrap();
-        steps += 1;
-        let current_node = map.nodes.get(current).unwrap();
-        if direction == 'L' {
-            current = current_node.left.as_str();
-        } else {
-            current = current_node.right.as_str();
-        }
-    }
+    let goal = map.index_of("ZZZ").unwrap();
+    let (steps, _) = map.walk(map.index_of("AAA").unwrap(), |node| node == goal).next().unwrap();
     return steps as i32;
 }
 
@@ -63,37 +121,180 @@ fn gcd_of_two_numbers(a: u64, b: u64) -> u64 {
     gcd_of_two_numbers(b, a % b)
 }
 
-#[aoc(day8, part2)]
-pub fn part2(map: &Map) -> u64 {
-    let map = map.to_owned();
-    let mut all: Vec<String> = vec![];
-    for name in map.nodes.keys() {
-        if name.ends_with('A') {
-            all.push(name.clone());
+// The walk of a ghost ends up in a cycle of (node, instruction index) states, after a tail of steps that are not part
+//  of the cycle. The hits are the steps at which the ghost is on a Z node, those from the tail happen only once while
+//  those in the cycle repeat every cycle steps.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Ghost {
+    pub start: String,
+    pub tail: usize,
+    pub cycle: usize,
+    pub tail_hits: Vec<usize>,
+    pub cycle_hits: Vec<usize>,
+}
+
+impl Ghost {
+    pub fn new(map: &Map, start: usize) -> Ghost {
+        // The cycle of states is a multiple of the number of instructions, so it is found by only remembering the
+        //  step at which each node was first seen at the start of the instructions. That state may be up to a whole
+        //  round of instructions into the cycle.
+        let length = map.instructions.len();
+        let mut seen: Vec<Option<usize>> = vec![None; map.names.len()];
+        let mut hits = vec![];
+        let (mut found, mut end) = (0, 0);
+        for (steps, node) in map.walk(start, |_| true) {
+            if steps % length == 0 {
+                if let Some(first) = seen[node] {
+                    (found, end) = (first, steps);
+                    break;
+                }
+                seen[node] = Some(steps);
+            }
+            if map.name(node).ends_with('Z') {
+                hits.push(steps);
+            }
         }
-    }
+        let cycle = end - found;
 
-    let steps: Vec<u64> = all.iter().map(|start| {
-        let mut current = start.to_string();
-        let mut steps: usize = 0;
-        while !current.ends_with('Z') {
-            let direction = map.instructions.chars().nth(steps % map.instructions.chars().count()).unwrap();
-            steps += 1;
-            let curr_node = map.nodes.get(current.as_str()).unwrap();
-            if direction == 'L' {
-                current = curr_node.left.to_string();
-            } else {
-                current = curr_node.right.to_string();
+        // A state is in the cycle when it equals the state a cycle later, and then so is every state after it, so the
+        //  tail ends where the last round of nodes before the found state stops matching the nodes a cycle later
+        let from = found.saturating_sub(length - 1);
+        let mut before = vec![];
+        let mut after = vec![];
+        for (steps, node) in map.walk(start, |_| true).take_while(|(steps, _)| *steps < found + cycle) {
+            if (from..found).contains(&steps) {
+                before.push(node);
+            }
+            if (from + cycle..found + cycle).contains(&steps) {
+                after.push(node);
             }
         }
-        return steps as u64;
-    }).collect();
-    return lcm(&steps);
+        let mut tail = found;
+        while tail > from && before[tail - 1 - from] == after[tail - 1 - from] {
+            tail -= 1;
+        }
+
+        let tail_hits = hits.iter().copied().filter(|hit| *hit < tail).collect();
+        let cycle_hits = hits.iter().copied().filter(|hit| (tail..tail + cycle).contains(hit)).collect();
+        return Ghost {
+            start: map.name(start).to_string(),
+            tail,
+            cycle,
+            tail_hits,
+            cycle_hits,
+        };
+    }
+
+    pub fn is_hit(&self, steps: usize) -> bool {
+        if steps < self.tail {
+            return self.tail_hits.contains(&steps);
+        }
+        return self.cycle_hits.contains(&(self.tail + (steps - self.tail) % self.cycle));
+    }
+
+    // whether the ghost is on a Z node exactly every n steps, where n is the number of steps to its first Z node
+    pub fn hits_multiples_of_first(&self) -> bool {
+        let hits: Vec<usize> = self.tail_hits.iter().chain(self.cycle_hits.iter()).copied().collect();
+        let first = match hits.first() {
+            Some(first) if *first > 0 => *first,
+            _ => return false,
+        };
+        let multiples: Vec<usize> = (1..).map(|k| k * first).take_while(|m| *m < self.tail + self.cycle).collect();
+        return self.cycle.is_multiple_of(first) && hits == multiples;
+    }
+}
+
+#[derive(Debug)]
+pub struct GhostAnalysis {
+    pub ghosts: Vec<Ghost>,
+    pub shortcut_holds: bool,
+    pub steps: Option<u64>,
+}
+
+// combines x = a (mod m) and x = b (mod n) into x = c (mod lcm(m, n)), if such an x exists
+fn combine_congruences((a, m): (i128, i128), (b, n): (i128, i128)) -> Option<(i128, i128)> {
+    // solve a + m * k = b (mod n), which requires the difference to be divisible by gcd(m, n)
+    let (g, p, _) = extended_gcd(m, n);
+    if (b - a) % g != 0 {
+        return None;
+    }
+    let lcm = m / g * n;
+    let k = ((b - a) / g % (n / g)) * p % (n / g);
+    return Some(((a + m * k).rem_euclid(lcm), lcm));
+}
+
+// returns (g, x, y) such that a * x + b * y = g = gcd(a, b)
+fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    if b == 0 {
+        return (a, 1, 0);
+    }
+    let (g, x, y) = extended_gcd(b, a % b);
+    (g, y, x - (a / b) * y)
+}
+
+// returns the offsets of the hits in the cycle of a ghost modulo the smallest period after which they repeat, so that
+//  hits at 3 and 6 in a cycle of 6 become the single offset 0 modulo 3
+fn merge_offsets(ghost: &Ghost) -> (Vec<usize>, usize) {
+    let mut offsets: Vec<usize> = ghost.cycle_hits.iter().map(|hit| hit % ghost.cycle).collect();
+    offsets.sort_unstable();
+    let mut periods: Vec<usize> = (1..).take_while(|divisor| divisor * divisor <= ghost.cycle)
+        .filter(|divisor| ghost.cycle.is_multiple_of(*divisor))
+        .flat_map(|divisor| [divisor, ghost.cycle / divisor])
+        .collect();
+    periods.sort_unstable();
+    let period = periods.into_iter()
+        .find(|period| offsets.iter().all(|offset| offsets.binary_search(&((offset + period) % ghost.cycle)).is_ok()))
+        .unwrap();
+    return (offsets.into_iter().filter(|offset| *offset < period).collect(), period);
+}
+
+pub fn analyze_ghosts(map: &Map) -> GhostAnalysis {
+    let mut starts: Vec<usize> = (0..map.names.len()).filter(|node| map.name(*node).ends_with('A')).collect();
+    starts.sort_by_key(|node| map.name(*node));
+    let ghosts: Vec<Ghost> = starts.iter().map(|start| Ghost::new(map, *start)).collect();
+    let shortcut_holds = ghosts.iter().all(|ghost| ghost.hits_multiples_of_first());
+    let longest_tail = ghosts.iter().map(|ghost| ghost.tail).max().unwrap_or(0);
+
+    // before all ghosts are in their cycle, check each hit of the first ghost
+    let Some(first) = ghosts.first() else {
+        return GhostAnalysis { ghosts, shortcut_holds, steps: None };
+    };
+    let early = first.tail_hits.iter().copied()
+        .chain(first.cycle_hits.iter().flat_map(|hit| (*hit..longest_tail).step_by(first.cycle)))
+        .filter(|steps| *steps < longest_tail && ghosts.iter().all(|ghost| ghost.is_hit(*steps)))
+        .min();
+    if let Some(steps) = early {
+        return GhostAnalysis { ghosts, shortcut_holds, steps: Some(steps as u64) };
+    }
+
+    // after that, each ghost hits at some offsets within each cycle, so combine every choice of offsets. The number of
+    //  choices is the product of the number of offsets of each ghost, which is kept down by first merging the offsets
+    //  of a ghost that repeat within its cycle.
+    let mut solutions: Vec<(i128, i128)> = vec![(0, 1)];
+    for ghost in ghosts.iter() {
+        let (offsets, modulus) = merge_offsets(ghost);
+        solutions = solutions.iter().flat_map(|solution| offsets.iter().filter_map(|offset| {
+            combine_congruences(*solution, (*offset as i128, modulus as i128))
+        }).collect::<Vec<(i128, i128)>>()).collect();
+    }
+    let steps = solutions.iter().map(|(remainder, modulus)| {
+        let longest_tail = longest_tail as i128;
+        if *remainder >= longest_tail {
+            return *remainder;
+        }
+        return remainder + (longest_tail - remainder + modulus - 1) / modulus * modulus;
+    }).min().map(|steps| steps as u64);
+    return GhostAnalysis { ghosts, shortcut_holds, steps };
+}
+
+#[aoc(day8, part2)]
+pub fn part2(map: &Map) -> u64 {
+    return analyze_ghosts(map).steps.expect("the ghosts are never on a Z node at the same time");
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{input_generator, Map, part1, part2};
+    use super::{analyze_ghosts, Ghost, input_generator, Map, merge_offsets, part1, part2};
 
     fn example() -> Map {
         return input_generator("LLR\n\nAAA = (BBB, BBB)\nBBB = (AAA, ZZZ)\nZZZ = (ZZZ, ZZZ)");
@@ -105,11 +306,69 @@ mod tests {
         assert_eq!(part1(&example()), 6);
     }
 
+    #[test]
+    fn dot() {
+        assert_eq!(example().to_dot(), "digraph G {
+  AAA [color=green];
+  BBB;
+  ZZZ [color=red];
+  AAA -> BBB [label=L];
+  AAA -> BBB [label=R];
+  BBB -> AAA [label=L];
+  BBB -> ZZZ [label=R];
+  ZZZ -> ZZZ [label=L];
+  ZZZ -> ZZZ [label=R];
+}
+");
+    }
+
     // part 2
     #[test]
     fn sample2() {
         let input = "LR\n\n11A = (11B, XXX)\n11B = (XXX, 11Z)\n11Z = (11B, XXX)\n22A = (22B, XXX)\n22B = (22C, 22C)\n22C = (22Z, 22Z)\n22Z = (22B, 22B)\nXXX = (XXX, XXX)";
         assert_eq!(part2(&input_generator(input)), 6);
+
+        let analysis = analyze_ghosts(&input_generator(input));
+        assert_eq!(analysis.ghosts, vec![
+            Ghost { start: String::from("11A"), tail: 1, cycle: 2, tail_hits: vec![], cycle_hits: vec![2] },
+            Ghost { start: String::from("22A"), tail: 1, cycle: 6, tail_hits: vec![], cycle_hits: vec![3, 6] },
+        ]);
+        assert!(analysis.shortcut_holds);
+        assert_eq!(merge_offsets(&analysis.ghosts[0]), (vec![0], 2));
+        assert_eq!(merge_offsets(&analysis.ghosts[1]), (vec![0], 3));
+
+        // a network without any ghosts has no answer
+        let analysis = analyze_ghosts(&input_generator("L\n\nBBB = (BBB, BBB)"));
+        assert!(analysis.ghosts.is_empty());
+        assert_eq!(analysis.steps, None);
+    }
+
+    #[test]
+    fn walk() {
+        let map = example();
+        let walked: Vec<(usize, &str)> = map.walk(map.index_of("AAA").unwrap(), |_| true)
+            .take(7)
+            .map(|(step, node)| (step, map.name(node)))
+            .collect();
+        assert_eq!(walked, vec![(0, "AAA"), (1, "BBB"), (2, "AAA"), (3, "BBB"), (4, "AAA"), (5, "BBB"), (6, "ZZZ")]);
+        let bbb = map.index_of("BBB").unwrap();
+        assert_eq!(map.walk(map.index_of("AAA").unwrap(), |node| node == bbb).take(3).map(|(step, _)| step).collect::<Vec<usize>>(), vec![1, 3, 5]);
+    }
+
+    #[test]
+    fn cycles() {
+        // the first ghost hits Z after 2 steps and then every 3 steps, the second after 3 steps and then every 2 steps
+        let input = "L\n\n11A = (11B, 11B)\n11B = (11Z, 11Z)\n11Z = (11C, 11C)\n11C = (11D, 11D)\n11D = (11Z, 11Z)
+22A = (22B, 22B)\n22B = (22C, 22C)\n22C = (22Z, 22Z)\n22Z = (22D, 22D)\n22D = (22Z, 22Z)";
+        let analysis = analyze_ghosts(&input_generator(input));
+        assert!(!analysis.shortcut_holds);
+        assert_eq!(analysis.steps, Some(5));
+
+        // ghosts that hit in their tail, or never at the same time
+        let input = "L\n\n11A = (11Z, 11Z)\n11Z = (11B, 11B)\n11B = (11B, 11B)\n22A = (22Z, 22Z)\n22Z = (22Z, 22Z)";
+        assert_eq!(analyze_ghosts(&input_generator(input)).steps, Some(1));
+        let input = "L\n\n11A = (11Z, 11Z)\n11Z = (11A, 11A)\n22A = (22B, 22B)\n22B = (22Z, 22Z)\n22Z = (22B, 22B)";
+        assert_eq!(analyze_ghosts(&input_generator(input)).steps, None);
     }
 
 }
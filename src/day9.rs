@@ -1,48 +1,68 @@
 pub struct History {
-    data: Vec<i32>,
+    data: Vec<i128>,
+    differences: Vec<i128>,
 }
 
 impl History {
     pub fn new(input: &str) -> History {
-        History {
-            data: input.split(' ').map(|v| v.parse().unwrap()).collect(),
+        let data: Vec<i128> = input.split_whitespace().map(|v| v.parse().unwrap()).collect();
+
+        // keep the first value of each row of the difference table, until a row is constant
+        let mut differences = vec![];
+        let mut row = data.clone();
+        while let Some(first) = row.first() {
+            differences.push(*first);
+            if row.iter().all(|v| v == first) {
+                break;
+            }
+            row = row.windows(2).map(|pair| pair[1] - pair[0]).collect();
         }
+        History { data, differences }
     }
 
-    pub fn predict(&self) -> i32 {
-        let derivatives: Vec<i32> = vec![0; self.data.len() - 1].iter().enumerate()
-            .map(|(index, _)| self.data[index + 1] - self.data[index]).collect();
-        let last =  *self.data.get(self.data.len() - 1).unwrap();
-        if derivatives.iter().all(|v| *v == 0) {
-            return last;
-        }
-        return (History { data: derivatives }).predict() + last;
+    // returns the degree of the polynomial through the data, which is only certain if it is lower than the number of
+    //  values minus one
+    pub fn degree(&self) -> usize {
+        return self.differences.len().saturating_sub(1);
     }
 
-    pub fn predict_back(&self) -> i32 {
-        let derivatives: Vec<i32> = vec![0; self.data.len() - 1].iter().enumerate()
-            .map(|(index, _)| self.data[index + 1] - self.data[index]).collect();
-        let first =  *self.data.first().unwrap();
-        if derivatives.iter().all(|v| *v == 0) {
-            return first;
+    // returns the value at offset k from the first value, so the value after the data is at k = len and the value
+    //  before it at k = -1, using Newton's forward difference formula f(k) = sum of C(k, j) * the j-th difference
+    pub fn value_at(&self, k: i128) -> Option<i128> {
+        let mut binomial: i128 = 1;
+        let mut value: i128 = 0;
+        for (j, difference) in self.differences.iter().enumerate() {
+            if j > 0 {
+                // C(k, j) = C(k, j - 1) * (k - j + 1) / j, which divides exactly
+                binomial = binomial.checked_mul(k.checked_sub(j as i128 - 1)?)? / j as i128;
+            }
+            value = value.checked_add(binomial.checked_mul(*difference)?)?;
         }
-        return first - (History { data: derivatives }).predict_back();
+        return Some(value);
+    }
+
+    pub fn predict(&self) -> i128 {
+        return self.value_at(self.data.len() as i128).unwrap();
+    }
+
+    pub fn predict_back(&self) -> i128 {
+        return self.value_at(-1).unwrap();
     }
 }
 
 #[aoc(day9, part1)]
-pub fn part1(lines: &str) -> i32 {
+pub fn part1(lines: &str) -> i128 {
     return lines.lines().map(History::new).map(|history| history.predict()).sum();
 }
 
 #[aoc(day9, part2)]
-pub fn part2(lines: &str) -> i32 {
+pub fn part2(lines: &str) -> i128 {
     return lines.lines().map(History::new).map(|history| history.predict_back()).sum();
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{part1, part2};
+    use super::{History, part1, part2};
 
     // part 1
     #[test]
@@ -56,4 +76,26 @@ mod tests {
         assert_eq!(part2("0 3 6 9 12 15\n1 3 6 10 15 21\n10 13 16 21 30 45"), 2);
     }
 
+    #[test]
+    fn extrapolation() {
+        let history = History::new("10 13 16 21 30 45");
+        assert_eq!(history.degree(), 3);
+        assert_eq!(history.value_at(0), Some(10));
+        assert_eq!(history.value_at(5), Some(45));
+        assert_eq!(history.value_at(6), Some(68));
+        assert_eq!(history.value_at(-1), Some(5));
+        assert_eq!(history.value_at(-2), Some(-4));
+
+        // n^2 + 1 far from the data
+        let history = History::new("1 2 5 10 17");
+        assert_eq!(history.degree(), 2);
+        assert_eq!(history.value_at(1_000_000), Some(1_000_000_000_001));
+        assert_eq!(history.value_at(-1_000_000_000_000), Some(1_000_000_000_000_000_000_000_001));
+        assert_eq!(history.value_at(i128::MAX), None);
+        assert_eq!(history.value_at(i128::MIN), None);
+
+        assert_eq!(History::new("7 7 7").degree(), 0);
+        assert_eq!(History::new("7 7 7").value_at(-100), Some(7));
+        assert_eq!(History::new("1 3").degree(), 1);
+    }
 }
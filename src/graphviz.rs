@@ -0,0 +1,124 @@
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Style {
+    pub color: Option<&'static str>,
+    pub shape: Option<&'static str>,
+}
+
+impl Style {
+    pub fn color(color: &'static str) -> Style {
+        Style { color: Some(color), shape: None }
+    }
+
+    pub fn with_shape(self, shape: &'static str) -> Style {
+        Style { shape: Some(shape), ..self }
+    }
+}
+
+#[derive(Debug)]
+struct Node {
+    name: String,
+    label: Option<String>,
+    style: Style,
+}
+
+#[derive(Debug)]
+struct Edge {
+    from: String,
+    to: String,
+    label: Option<String>,
+}
+
+// A graph that can be rendered in the graphviz DOT language, nodes and edges are written in the order they are added
+#[derive(Debug)]
+pub struct Graph {
+    directed: bool,
+    nodes: Vec<Node>,
+    edges: Vec<Edge>,
+}
+
+impl Graph {
+    pub fn directed() -> Graph {
+        Graph { directed: true, nodes: vec![], edges: vec![] }
+    }
+
+    pub fn undirected() -> Graph {
+        Graph { directed: false, nodes: vec![], edges: vec![] }
+    }
+
+    pub fn node(&mut self, name: &str, label: Option<String>, style: Style) -> &mut Graph {
+        self.nodes.push(Node { name: name.to_string(), label, style });
+        return self;
+    }
+
+    pub fn edge(&mut self, from: &str, to: &str, label: Option<String>) -> &mut Graph {
+        self.edges.push(Edge { from: from.to_string(), to: to.to_string(), label });
+        return self;
+    }
+
+    pub fn to_dot(&self) -> String {
+        fn attributes(pairs: &[(&str, Option<&str>)]) -> String {
+            let pairs: Vec<String> = pairs.iter()
+                .filter_map(|(key, value)| value.map(|value| format!("{}={}", key, quote(value))))
+                .collect();
+            if pairs.is_empty() {
+                return String::new();
+            }
+            return format!(" [{}]", pairs.join(", "));
+        }
+        let mut dot = String::new();
+        let (keyword, arrow) = if self.directed { ("digraph", "->") } else { ("graph", "--") };
+        writeln!(dot, "{} G {{", keyword).unwrap();
+        self.nodes.iter().for_each(|node| {
+            writeln!(dot, "  {}{};", quote(&node.name), attributes(&[
+                ("label", node.label.as_deref()),
+                ("color", node.style.color),
+                ("shape", node.style.shape),
+            ])).unwrap();
+        });
+        self.edges.iter().for_each(|edge| {
+            writeln!(dot, "  {} {} {}{};", quote(&edge.from), arrow, quote(&edge.to), attributes(&[
+                ("label", edge.label.as_deref()),
+            ])).unwrap();
+        });
+        dot.push_str("}\n");
+        return dot;
+    }
+}
+
+// quotes an identifier, unless it is already a valid plain DOT identifier
+fn quote(value: &str) -> String {
+    let is_plain = !value.is_empty()
+        && !value.starts_with(|c: char| c.is_ascii_digit())
+        && value.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    let is_numeral = !value.is_empty() && value.chars().all(|c| c.is_ascii_digit());
+    if is_plain || is_numeral {
+        return value.to_string();
+    }
+    return format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Graph, Style};
+
+    #[test]
+    fn dot() {
+        let mut graph = Graph::directed();
+        graph.node("begin", None, Style::color("green"))
+            .node("12", Some(String::from("length 3")), Style::default().with_shape("box"))
+            .edge("begin", "12", None)
+            .edge("12", "a b", Some(String::from("R")));
+        assert_eq!(graph.to_dot(), "digraph G {
+  begin [color=green];
+  12 [label=\"length 3\", shape=box];
+  begin -> 12;
+  12 -> \"a b\" [label=R];
+}
+");
+        let mut graph = Graph::undirected();
+        graph.edge("a", "b", None);
+        assert_eq!(graph.to_dot(), "graph G {\n  a -- b;\n}\n");
+    }
+}
@@ -0,0 +1,141 @@
+use std::cmp;
+use std::ops::Range;
+
+// A set of integers stored as sorted half-open intervals, which never overlap or touch and are never empty
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntervalSet<T> {
+    intervals: Vec<Range<T>>,
+}
+
+impl<T: Copy + Ord> IntervalSet<T> {
+    pub fn new() -> IntervalSet<T> {
+        IntervalSet { intervals: vec![] }
+    }
+
+    pub fn from_range(range: Range<T>) -> IntervalSet<T> {
+        return IntervalSet::from_iter(std::iter::once(range));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.intervals.is_empty();
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Range<T>> {
+        return self.intervals.iter();
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        // find the last interval that starts at or before the value
+        let index = self.intervals.partition_point(|interval| interval.start <= value);
+        return index > 0 && value < self.intervals[index - 1].end;
+    }
+
+    pub fn insert(&mut self, range: Range<T>) {
+        *self = self.union(&IntervalSet::from_range(range));
+    }
+
+    pub fn union(&self, other: &IntervalSet<T>) -> IntervalSet<T> {
+        return self.iter().chain(other.iter()).cloned().collect();
+    }
+
+    pub fn intersection(&self, other: &IntervalSet<T>) -> IntervalSet<T> {
+        let mut intervals = vec![];
+        let (mut a, mut b) = (0, 0);
+        while a < self.intervals.len() && b < other.intervals.len() {
+            let (left, right) = (&self.intervals[a], &other.intervals[b]);
+            let start = cmp::max(left.start, right.start);
+            let end = cmp::min(left.end, right.end);
+            if start < end {
+                intervals.push(start..end);
+            }
+
+            // move past whichever interval ends first, as it cannot overlap anything further
+            if left.end < right.end { a += 1; } else { b += 1; }
+        }
+        return IntervalSet { intervals };
+    }
+
+    pub fn difference(&self, other: &IntervalSet<T>) -> IntervalSet<T> {
+        let mut intervals = vec![];
+        let mut b = 0;
+        for interval in self.intervals.iter() {
+            let mut start = interval.start;
+
+            // skip the intervals that end before this one starts
+            while b < other.intervals.len() && other.intervals[b].end <= start {
+                b += 1;
+            }
+
+            // cut out each interval that starts before this one ends
+            let mut index = b;
+            while index < other.intervals.len() && other.intervals[index].start < interval.end {
+                let removed = &other.intervals[index];
+                if start < removed.start {
+                    intervals.push(start..removed.start);
+                }
+                start = cmp::max(start, removed.end);
+                index += 1;
+            }
+            if start < interval.end {
+                intervals.push(start..interval.end);
+            }
+        }
+        return IntervalSet { intervals };
+    }
+}
+
+impl<T: Copy + Ord> Default for IntervalSet<T> {
+    fn default() -> IntervalSet<T> {
+        IntervalSet::new()
+    }
+}
+
+impl<T: Copy + Ord> FromIterator<Range<T>> for IntervalSet<T> {
+    fn from_iter<I: IntoIterator<Item = Range<T>>>(ranges: I) -> IntervalSet<T> {
+        let mut ranges: Vec<Range<T>> = ranges.into_iter().filter(|range| range.start < range.end).collect();
+        ranges.sort_by_key(|range| range.start);
+        let mut intervals: Vec<Range<T>> = vec![];
+        for range in ranges {
+            match intervals.last_mut() {
+                Some(last) if range.start <= last.end => last.end = cmp::max(last.end, range.end),
+                _ => intervals.push(range),
+            }
+        }
+        return IntervalSet { intervals };
+    }
+}
+
+impl<'a, T> IntoIterator for &'a IntervalSet<T> {
+    type Item = &'a Range<T>;
+    type IntoIter = std::slice::Iter<'a, Range<T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        return self.intervals.iter();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IntervalSet;
+
+    #[test]
+    fn operations() {
+        let a: IntervalSet<i64> = IntervalSet::from_iter([30..50, 0..10, 12..20, 18..22, 35..45, 10..11, 60..60]);
+        assert_eq!(a.iter().cloned().collect::<Vec<_>>(), vec![0..11, 12..22, 30..50]);
+        assert!(a.contains(0) && a.contains(10) && a.contains(49));
+        assert!(!a.contains(11) && !a.contains(22) && !a.contains(-1) && !a.contains(50));
+
+        let b: IntervalSet<i64> = IntervalSet::from_iter([5..15, 40..60]);
+        assert_eq!(a.union(&b).iter().cloned().collect::<Vec<_>>(), vec![0..22, 30..60]);
+        assert_eq!(a.intersection(&b).iter().cloned().collect::<Vec<_>>(), vec![5..11, 12..15, 40..50]);
+        assert_eq!(a.difference(&b).iter().cloned().collect::<Vec<_>>(), vec![0..5, 15..22, 30..40]);
+        assert_eq!(b.difference(&a).iter().cloned().collect::<Vec<_>>(), vec![11..12, 50..60]);
+        assert!(a.difference(&a).is_empty());
+
+        let mut c = IntervalSet::<u8>::new();
+        c.insert(250..255);
+        c.insert(0..3);
+        c.insert(3..5);
+        assert_eq!((&c).into_iter().cloned().collect::<Vec<_>>(), vec![0..5, 250..255]);
+    }
+}
@@ -28,5 +28,9 @@ pub mod day22;
 pub mod day23;
 pub mod day24;
 pub mod day25;
+pub mod graphviz;
+pub mod interval_set;
+pub mod nonogram;
+pub mod polygon;
 
 aoc_lib!{ year = 2023 }
@@ -0,0 +1,172 @@
+// A solver for nonograms, where each row and column of the grid is a day 12 row of springs: the clue of a line lists
+//  the sizes of its groups of filled cells in order, and a cell that is not known yet is a ?.
+use std::fmt;
+use crate::day12::count_arrangements;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Grid {
+    width: usize,
+    height: usize,
+    cells: Vec<u8>,
+}
+
+impl Grid {
+    fn unknown(width: usize, height: usize) -> Grid {
+        return Grid { width, height, cells: vec![b'?'; width * height] };
+    }
+
+    pub fn width(&self) -> usize {
+        return self.width;
+    }
+
+    pub fn height(&self) -> usize {
+        return self.height;
+    }
+
+    pub fn is_filled(&self, x: usize, y: usize) -> bool {
+        return self.cells[y * self.width + x] == b'#';
+    }
+
+    // the indices of the cells of a line, where the rows come first and the columns after them
+    fn line(&self, line: usize) -> Vec<usize> {
+        if line < self.height {
+            return (0..self.width).map(|x| line * self.width + x).collect();
+        }
+        return (0..self.height).map(|y| y * self.width + (line - self.height)).collect();
+    }
+}
+
+impl fmt::Display for Grid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // a grid without columns still has its (empty) rows
+        for y in 0..self.height {
+            writeln!(f, "{}", String::from_utf8_lossy(&self.cells[y * self.width..(y + 1) * self.width]))?;
+        }
+        return Ok(());
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Outcome {
+    Solved(Grid),
+    // two different grids that both match the clues
+    Ambiguous(Grid, Grid),
+    Unsolvable,
+}
+
+// Repeatedly solves single lines until nothing changes: a cell is fixed when every arrangement of its line that is
+//  still possible agrees on it. Returns false when some line has no arrangements left.
+fn propagate(grid: &mut Grid, clues: &[&[usize]]) -> bool {
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (line, clue) in clues.iter().enumerate() {
+            let indices = grid.line(line);
+            let mut cells: Vec<u8> = indices.iter().map(|index| grid.cells[*index]).collect();
+            let total = count_arrangements(&cells, clue);
+            if total == 0 {
+                return false;
+            }
+            for (position, index) in indices.iter().enumerate() {
+                if cells[position] != b'?' {
+                    continue;
+                }
+                cells[position] = b'#';
+                let filled = count_arrangements(&cells, clue);
+                cells[position] = if filled == total { b'#' } else if filled == 0 { b'.' } else { b'?' };
+                if cells[position] != b'?' {
+                    grid.cells[*index] = cells[position];
+                    changed = true;
+                }
+            }
+        }
+    }
+    return true;
+}
+
+// Finds up to two solutions, guessing the first unknown cell whenever line solving gets stuck
+fn search(mut grid: Grid, clues: &[&[usize]], solutions: &mut Vec<Grid>) {
+    if !propagate(&mut grid, clues) {
+        return;
+    }
+    let Some(index) = grid.cells.iter().position(|cell| *cell == b'?') else {
+        solutions.push(grid);
+        return;
+    };
+    for guess in [b'#', b'.'] {
+        if solutions.len() > 1 {
+            return;
+        }
+        let mut guessed = grid.clone();
+        guessed.cells[index] = guess;
+        search(guessed, clues, solutions);
+    }
+}
+
+pub fn solve(rows: &[Vec<usize>], columns: &[Vec<usize>]) -> Outcome {
+    let clues: Vec<&[usize]> = rows.iter().chain(columns.iter()).map(|clue| clue.as_slice()).collect();
+    let mut solutions = vec![];
+    search(Grid::unknown(columns.len(), rows.len()), &clues, &mut solutions);
+    let mut solutions = solutions.into_iter();
+    return match (solutions.next(), solutions.next()) {
+        (Some(first), Some(second)) => Outcome::Ambiguous(first, second),
+        (Some(solution), None) => Outcome::Solved(solution),
+        _ => Outcome::Unsolvable,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{solve, Outcome};
+
+    // returns the row and column clues of a picture
+    fn clues(picture: &str) -> (Vec<Vec<usize>>, Vec<Vec<usize>>) {
+        let rows: Vec<&[u8]> = picture.lines().map(|line| line.as_bytes()).collect();
+        let groups = |cells: Vec<u8>| -> Vec<usize> {
+            return cells.split(|cell| *cell != b'#').map(|group| group.len()).filter(|size| *size > 0).collect();
+        };
+        return (
+            rows.iter().map(|row| groups(row.to_vec())).collect(),
+            (0..rows[0].len()).map(|x| groups(rows.iter().map(|row| row[x]).collect())).collect(),
+        );
+    }
+
+    #[test]
+    fn puzzles() {
+        let picture = "..##...
+.####..
+##.###.
+#######
+.#####.
+..###..
+...#...
+";
+        let (rows, columns) = clues(picture);
+        assert_eq!(rows[2], vec![2, 3]);
+        let Outcome::Solved(grid) = solve(&rows, &columns) else { panic!("expected a single solution") };
+        assert_eq!(grid.to_string(), picture);
+        assert_eq!((grid.width(), grid.height()), (7, 7));
+        assert!(grid.is_filled(2, 0));
+        assert!(!grid.is_filled(2, 2));
+
+        // a diagonal can go either way, which line solving alone cannot decide
+        match solve(&[vec![1], vec![1]], &[vec![1], vec![1]]) {
+            Outcome::Ambiguous(first, second) => {
+                assert_eq!(first.to_string(), "#.\n.#\n");
+                assert_eq!(second.to_string(), ".#\n#.\n");
+            },
+            outcome => panic!("expected an ambiguous puzzle, got {:?}", outcome),
+        }
+
+        // the rows fill three cells but the columns only two
+        assert_eq!(solve(&[vec![2], vec![1]], &[vec![1], vec![1]]), Outcome::Unsolvable);
+        assert_eq!(solve(&[vec![3]], &[vec![1], vec![1]]), Outcome::Unsolvable);
+
+        let (rows, columns) = clues("...\n...\n");
+        let Outcome::Solved(grid) = solve(&rows, &columns) else { panic!("expected a single solution") };
+        assert_eq!(grid.to_string(), "...\n...\n");
+        let Outcome::Solved(grid) = solve(&[vec![]], &[]) else { panic!("expected a single solution") };
+        assert_eq!(grid.to_string(), "\n");
+        assert_eq!(solve(&[vec![1]], &[]), Outcome::Unsolvable);
+    }
+}
@@ -0,0 +1,65 @@
+// Helpers for simple polygons on the integer grid, given by their vertices in order around the polygon. The polygon is
+//  closed, so the last vertex connects back to the first one.
+
+// returns twice the area of the polygon using the shoelace formula, which is positive when the vertices go clockwise
+//  with y pointing down
+pub fn double_signed_area(vertices: &[(i64, i64)]) -> i64 {
+    return (0..vertices.len()).map(|i| {
+        let (ax, ay) = vertices[i];
+        let (bx, by) = vertices[(i + 1) % vertices.len()];
+        ax * by - bx * ay
+    }).sum();
+}
+
+// returns the number of grid points on the edges of the polygon
+pub fn boundary_points(vertices: &[(i64, i64)]) -> i64 {
+    fn gcd(a: i64, b: i64) -> i64 {
+        if b == 0 {
+            return a.abs();
+        }
+        gcd(b, a % b)
+    }
+    return (0..vertices.len()).map(|i| {
+        let (ax, ay) = vertices[i];
+        let (bx, by) = vertices[(i + 1) % vertices.len()];
+        gcd(bx - ax, by - ay)
+    }).sum();
+}
+
+// returns the number of grid points strictly inside the polygon using Pick's theorem: area = inside + boundary / 2 - 1,
+//  which does not hold for fewer than 3 vertices or vertices on a single line, as those enclose nothing
+pub fn interior_points(vertices: &[(i64, i64)]) -> i64 {
+    let double_area = double_signed_area(vertices).abs();
+    if vertices.len() < 3 || double_area == 0 {
+        return 0;
+    }
+    return (double_area - boundary_points(vertices)) / 2 + 1;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{boundary_points, double_signed_area, interior_points};
+
+    #[test]
+    fn polygons() {
+        let square = [(0, 0), (4, 0), (4, 4), (0, 4)];
+        assert_eq!(double_signed_area(&square), 32);
+        assert_eq!(boundary_points(&square), 16);
+        assert_eq!(interior_points(&square), 9);
+
+        let counter_clockwise: Vec<(i64, i64)> = square.iter().rev().copied().collect();
+        assert_eq!(double_signed_area(&counter_clockwise), -32);
+        assert_eq!(interior_points(&counter_clockwise), 9);
+
+        let triangle = [(0, 0), (4, 0), (0, 2)];
+        assert_eq!(double_signed_area(&triangle), 8);
+        assert_eq!(boundary_points(&triangle), 8);
+        assert_eq!(interior_points(&triangle), 1);
+
+        // no polygon, a single point, a line and a polygon folded onto a line
+        assert_eq!(interior_points(&[]), 0);
+        assert_eq!(interior_points(&[(1, 1)]), 0);
+        assert_eq!(interior_points(&[(0, 0), (3, 0)]), 0);
+        assert_eq!(interior_points(&[(0, 0), (2, 0), (1, 0)]), 0);
+    }
+}